@@ -0,0 +1,478 @@
+//! `azd!`, which turns a document into the Rust code building its `Dom` while the
+//! crate using it is compiled.
+//!
+//! Documents are loaded by `azul-document-loader-syntax`, like they are at runtime,
+//! so they mean exactly the same thing either way.
+
+extern crate proc_macro;
+
+use azul_document_loader_syntax::bindings::interpolate;
+use azul_document_loader_syntax::error::LoadError;
+use azul_document_loader_syntax::load;
+use azul_document_loader_syntax::parse::{
+    check_attribute, ASTPoint, Declaration, NType, Node, TextArgument, Value,
+};
+use azul_document_loader_syntax::span::{Span, Spanned};
+use azul_document_loader_syntax::tokenize::Number;
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use quote::{quote, quote_spanned};
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::Path;
+use syn::LitStr;
+
+/// The name inline documents are loaded under, relative to the crate's root.
+const INLINE: &str = "azd!";
+
+/// Parses a document at compile time, and expands to an `Embedded` document: its texts,
+/// and a function which builds its `Dom` without anything left to parse or walk.
+///
+/// Takes either the path of a document relative to the crate's root:
+/// `azd!("layout.azd")`, or the document itself: `azd!{ div#root[ ... ] }`.
+/// Problems with the document are reported as compiler errors.
+#[proc_macro]
+pub fn azd(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = TokenStream::from(input);
+    let root = env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let output = match syn::parse2::<LitStr>(input.clone()) {
+        Ok(path) => from_file(&path, &root),
+        Err(_) => from_tokens(input, &root),
+    };
+    output.into()
+}
+
+fn from_file(path: &LitStr, root: &str) -> TokenStream {
+    let full = Path::new(root).join(path.value());
+    let name = full.to_string_lossy();
+    let source = match fs::read_to_string(&full) {
+        Ok(x) => x,
+        Err(e) => {
+            let message = format!("could not read {}: {}", name, e);
+            return quote_spanned!(path.span()=> compile_error!(#message));
+        }
+    };
+    match load::load_named(&source, &name) {
+        Ok(x) => Quoter::new(root).document(&x),
+        Err(e) => {
+            let message = e.render(&source);
+            quote_spanned!(path.span()=> compile_error!(#message))
+        }
+    }
+}
+
+/// The text of a document written inside of the macro, along with where each of
+/// its tokens came from so that problems can be pointed out in the Rust source.
+struct Inline {
+    text: String,
+    spans: Vec<(usize, proc_macro2::Span)>,
+    /// Whether the text ends with a node's type or selectors, after which a
+    /// `{` opens a style block rather than a comment.
+    head: bool,
+    errors: Vec<TokenStream>,
+}
+
+impl Inline {
+    /// Pushes `token`, returning whether it was joined to the one before.
+    fn push(&mut self, token: &str, span: proc_macro2::Span) -> bool {
+        // Rust splits `#id`, `.class` and `some-name` into several tokens.
+        let joined = self.text.ends_with(&['#', '.', '-', '+'][..]) || token == "-" || token == "%";
+        if !joined && !self.text.is_empty() {
+            self.text.push(' ');
+        }
+        self.spans.push((self.text.len(), span));
+        self.text += token;
+        joined
+    }
+
+    /// Pushes a name or number, which ends a node's head if it's a node type or
+    /// the name in a selector.
+    fn push_name(&mut self, token: &str, span: proc_macro2::Span) {
+        let selector =
+            self.text.ends_with(&['#', '.'][..]) || (self.head && self.text.ends_with('-'));
+        let joined = self.push(token, span);
+        self.head = ["div", "label", "image", "text"].contains(&token) || (joined && selector);
+    }
+
+    fn write(&mut self, stream: TokenStream) {
+        for tree in stream {
+            match tree {
+                TokenTree::Group(x) => {
+                    let (open, close) = match x.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::None => {
+                            self.write(x.stream());
+                            continue;
+                        }
+                    };
+                    // Rust doesn't say whether there was a space before the `{`,
+                    // so `div{...}` and `div {...}` can't be told apart.
+                    if self.head && x.delimiter() == Delimiter::Brace {
+                        let message = "A `{` right after a node's type or selectors is a style block \
+                                       if it follows without a space, and a comment otherwise, \
+                                       which can't be told apart inside of `azd!`\n\n\
+                                       help: write the style as `style = \"...\"`, or move the comment";
+                        self.errors
+                            .push(quote_spanned!(x.span_open()=> compile_error!(#message)));
+                    }
+                    self.head = false;
+                    self.push(open, x.span_open());
+                    self.write(x.stream());
+                    self.push(close, x.span_close());
+                    self.head = false;
+                }
+                TokenTree::Ident(x) => self.push_name(&x.to_string(), x.span()),
+                TokenTree::Literal(x) => self.push_name(&x.to_string(), x.span()),
+                TokenTree::Punct(x) => {
+                    // `-` may go on with the name of a selector.
+                    let c = x.as_char();
+                    self.head &= c == '-';
+                    self.push(&c.to_string(), x.span());
+                }
+            }
+        }
+    }
+
+    /// The Rust token at byte `offset` of the text.
+    fn span_at(&self, offset: usize) -> proc_macro2::Span {
+        match self.spans.binary_search_by_key(&offset, |x| x.0) {
+            Ok(x) => self.spans[x].1,
+            Err(0) => proc_macro2::Span::call_site(),
+            Err(x) => self.spans[x - 1].1,
+        }
+    }
+}
+
+fn from_tokens(input: TokenStream, root: &str) -> TokenStream {
+    let mut inline = Inline {
+        text: String::new(),
+        spans: Vec::new(),
+        head: false,
+        errors: Vec::new(),
+    };
+    inline.write(input);
+    if !inline.errors.is_empty() {
+        let errors = &inline.errors;
+        return quote!({ #(#errors);* });
+    }
+    let name = Path::new(root).join(INLINE);
+    let error = match load::load_named(&inline.text, &name.to_string_lossy()) {
+        Ok(x) => return Quoter::new(root).document(&x),
+        Err(e) => e,
+    };
+    let errors: Vec<TokenStream> = match error {
+        // The include is the only part of it in this document.
+        LoadError::Included { ref from, .. } => {
+            let message = error.to_string();
+            vec![quote_spanned!(inline.span_at(from.start)=> compile_error!(#message))]
+        }
+        _ if error.diagnostics().is_empty() => {
+            let message = error.to_string();
+            vec![quote!(compile_error!(#message))]
+        }
+        _ => error
+            .diagnostics()
+            .iter()
+            .map(|x| {
+                let message = match x.help {
+                    Some(ref help) => format!("{}\n\nhelp: {}", x.message, help),
+                    None => x.message.clone(),
+                };
+                quote_spanned!(inline.span_at(x.span.start)=> compile_error!(#message))
+            })
+            .collect(),
+    };
+    quote!({ #(#errors);* })
+}
+
+/// Turns a syntax tree into the Rust code which builds its `Dom`.
+struct Quoter<'a> {
+    root: &'a str,
+    /// Every document the tree came from, so that the crate is rebuilt when one changes.
+    files: BTreeSet<String>,
+}
+
+impl<'a> Quoter<'a> {
+    fn new(root: &'a str) -> Quoter<'a> {
+        Quoter {
+            root,
+            files: BTreeSet::new(),
+        }
+    }
+
+    fn document(mut self, document: &(Vec<TextArgument>, ASTPoint)) -> TokenStream {
+        let texts: Vec<TokenStream> = document.0.iter().map(text).collect();
+        let root = match document.1 {
+            ASTPoint::Element(ref x) => self.node(x, &[]),
+            ASTPoint::Joint(ref x, ref body) => self.node(x, body),
+            ASTPoint::If(..) | ASTPoint::For(..) => {
+                error("An `if` or `for` can't be turned into a single node")
+            }
+            ref x => unexpanded(x),
+        };
+        let inline = Path::new(self.root).join(INLINE);
+        let files = self.files.iter().filter(|x| Path::new(x) != inline);
+        quote!({
+            #(const _: &[u8] = include_bytes!(#files);)*
+            fn texts() -> ::std::vec::Vec<::azul_document_loader::parse::TextArgument> {
+                ::std::vec![#(#texts),*]
+            }
+            #[allow(unused_mut, unused_variables)]
+            fn build<T: ::azul_document_loader::embedded::Layout>(
+                texts: &::std::collections::VecDeque<::azul_document_loader::embedded::TextId>,
+                info: &::azul_document_loader::embedded::WindowInfo<T>,
+                callbacks: &::azul_document_loader::CallbackRegistry<T>,
+                bindings: &dyn ::azul_document_loader::Bindings,
+            ) -> ::std::result::Result<
+                ::azul_document_loader::embedded::Dom<T>,
+                ::azul_document_loader::LoadError,
+            > {
+                ::std::result::Result::Ok(#root)
+            }
+            ::azul_document_loader::Embedded::new(texts, build)
+        })
+    }
+
+    /// A closure making `span`, for the errors found while building the `Dom`.
+    fn span(&mut self, span: &Span) -> TokenStream {
+        self.files.insert(span.file.to_string());
+        // Keep the paths of the machine the crate was built on out of the binary.
+        let file = Path::new(&*span.file)
+            .strip_prefix(self.root)
+            .map(|x| x.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| span.file.to_string());
+        let (line, column, start, end) = (span.line, span.column, span.start, span.end);
+        quote!(|| ::azul_document_loader::span::Span {
+            file: ::std::sync::Arc::from(#file),
+            line: #line,
+            column: #column,
+            start: #start,
+            end: #end,
+        })
+    }
+
+    /// A label showing `template`, which is only filled in at runtime if it has bindings.
+    fn label(&mut self, template: &str, span: &Span) -> TokenStream {
+        let label = match interpolate(template, &()) {
+            Ok(x) => quote!(::std::string::String::from(#x)),
+            Err(_) => {
+                let span = self.span(span);
+                quote!(::azul_document_loader::dom_create::fill_in(#template, bindings, #span)?)
+            }
+        };
+        quote!(::azul_document_loader::embedded::Dom::new(
+            ::azul_document_loader::embedded::NodeType::Label(#label)
+        ))
+    }
+
+    fn attribute(&mut self, key: &str, value: &Spanned<Value>) -> TokenStream {
+        if let Err(e) = check_attribute(key, value) {
+            let error = error(&e.to_string());
+            return quote!(#error;);
+        }
+        match (key, &value.node) {
+            ("id", Value::Str(x)) => {
+                let ids = x.split_whitespace();
+                quote!(#(dom = dom.with_id(#ids);)*)
+            }
+            ("class", Value::Str(x)) => {
+                let classes = x.split_whitespace();
+                quote!(#(dom = dom.with_class(#classes);)*)
+            }
+            ("tabindex", Value::Num(x)) => match x.index() {
+                Some(x) => quote!(dom = dom.with_tab_index(
+                    ::azul_document_loader::embedded::TabIndex::OverrideInParent(#x)
+                );),
+                None => TokenStream::new(),
+            },
+            ("tabindex", Value::Ident(_)) => {
+                quote!(dom = dom.with_tab_index(::azul_document_loader::embedded::TabIndex::Auto);)
+            }
+            ("tabindex", Value::Bool(_)) => {
+                quote!(dom = dom.with_tab_index(::azul_document_loader::embedded::TabIndex::NoKeyboardFocus);)
+            }
+            ("draggable", Value::Bool(x)) => quote!(dom = dom.is_draggable(#x);),
+            (_, Value::Ident(name)) => {
+                let span = self.span(&value.span);
+                quote!(dom = ::azul_document_loader::dom_create::callback(dom, #key, #name, callbacks, #span)?;)
+            }
+            // `check_attribute` lets nothing else through.
+            _ => TokenStream::new(),
+        }
+    }
+
+    fn style(&mut self, declaration: &Declaration, span: &Span) -> TokenStream {
+        let (property, value) = (&declaration.property, &declaration.value);
+        let span = self.span(span);
+        quote!(dom = ::azul_document_loader::dom_create::style(dom, #property, #value, #span)?;)
+    }
+
+    /// The expression building `node` and its children in `body`.
+    fn node(&mut self, node: &Node, body: &[ASTPoint]) -> TokenStream {
+        let dom = match node.ntype {
+            NType::Div => quote!(::azul_document_loader::embedded::Dom::new(
+                ::azul_document_loader::embedded::NodeType::Div
+            )),
+            NType::Label(ref x) => self.label(x, &node.span),
+            NType::BoundText(ref x) => self.label(&x.body, &node.span),
+            NType::Image(ref x) => {
+                let span = self.span(&node.span);
+                quote!(::azul_document_loader::dom_create::image(#x, info, #span)?)
+            }
+            NType::Text(x) => {
+                let span = self.span(&node.span);
+                quote!(::azul_document_loader::dom_create::text(#x, texts, #span)?)
+            }
+        };
+        let mut steps = Vec::new();
+        if let Some(ref x) = node.id {
+            steps.push(quote!(dom = dom.with_id(#x);));
+        }
+        for x in &node.classes {
+            steps.push(quote!(dom = dom.with_class(#x);));
+        }
+        for (key, value) in &node.attributes {
+            steps.push(self.attribute(key, value));
+        }
+        if let NType::BoundText(ref x) = node.ntype {
+            for declaration in x.declarations() {
+                steps.push(self.style(&declaration, &node.span));
+            }
+        }
+        for x in &node.style {
+            steps.push(self.style(&x.node, &x.span));
+        }
+        let children = self.children(body);
+        quote!({
+            let mut dom = #dom;
+            #(#steps)*
+            #children
+            dom
+        })
+    }
+
+    /// The statements adding the children in `body` to `dom`.
+    fn children(&mut self, body: &[ASTPoint]) -> TokenStream {
+        let children = body.iter().map(|x| match x {
+            ASTPoint::Element(x) => {
+                let x = self.node(x, &[]);
+                quote!(dom = dom.with_child(#x);)
+            }
+            ASTPoint::Joint(x, body) => {
+                let x = self.node(x, body);
+                quote!(dom = dom.with_child(#x);)
+            }
+            ASTPoint::If(x, body) => {
+                let (name, negated) = (&x.name, x.negated);
+                let body = self.children(body);
+                quote!(if bindings.is_true(#name) != #negated {
+                    #body
+                })
+            }
+            ASTPoint::For(x, body) => {
+                let (item, list) = (&x.item, &x.list);
+                let span = self.span(&x.span);
+                let body = self.children(body);
+                quote!(for record in &::azul_document_loader::dom_create::list(bindings, #list, #span)? {
+                    let scope = ::azul_document_loader::bindings::Scope {
+                        parent: bindings,
+                        item: #item,
+                        record,
+                    };
+                    let bindings: &dyn ::azul_document_loader::Bindings = &scope;
+                    #body
+                })
+            }
+            ASTPoint::Use(_) | ASTPoint::Include(_) => {
+                let error = unexpanded(x);
+                quote!(#error;)
+            }
+        });
+        children.collect()
+    }
+}
+
+/// A `compile_error!` expression, which statements have to end with a `;` themselves.
+fn error(message: &str) -> TokenStream {
+    quote!(compile_error!(#message))
+}
+
+/// Loading expands components and follows includes, so neither should be left.
+fn unexpanded(point: &ASTPoint) -> TokenStream {
+    match point {
+        ASTPoint::Use(x) => error(&format!("The component `{}` was never expanded", x.name)),
+        ASTPoint::Include(x) => error(&format!("{:?} was never included", x.path)),
+        _ => TokenStream::new(),
+    }
+}
+
+fn option(x: &Option<String>) -> TokenStream {
+    match x {
+        Some(x) => quote!(::std::option::Option::Some(::std::string::String::from(#x))),
+        None => quote!(::std::option::Option::None),
+    }
+}
+
+fn number(x: &Number) -> TokenStream {
+    let value = x.value;
+    let unit = match x.unit {
+        Some(x) => {
+            let x = proc_macro2::Ident::new(&format!("{:?}", x), proc_macro2::Span::call_site());
+            quote!(::std::option::Option::Some(::azul_document_loader::tokenize::Unit::#x))
+        }
+        None => quote!(::std::option::Option::None),
+    };
+    quote!(::azul_document_loader::tokenize::Number {
+        value: #value,
+        unit: #unit,
+    })
+}
+
+fn text(x: &TextArgument) -> TokenStream {
+    let body = &x.body;
+    let font = option(&x.font);
+    let number = |x: &Option<Number>| match x {
+        Some(x) => {
+            let x = number(x);
+            quote!(::std::option::Option::Some(#x))
+        }
+        None => quote!(::std::option::Option::None),
+    };
+    let size = number(&x.size);
+    quote!(::azul_document_loader::parse::TextArgument {
+        body: ::std::string::String::from(#body),
+        font: #font,
+        size: #size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(source: &str) -> String {
+        from_tokens(source.parse().unwrap(), "").to_string()
+    }
+
+    #[test]
+    fn braces_after_a_node_head_are_errors() {
+        for source in &[
+            "div{ width: 100px; }",
+            "label.title{ color: blue; }(\"Hello\");",
+            "div#side-bar{ width: 100px; }",
+            "div.col-2 { A comment }",
+        ] {
+            assert!(expand(source).contains("compile_error"), "{}", source);
+        }
+        // Anywhere else they are comments.
+        for source in &[
+            "div[ {A comment} label(\"x\"); ]",
+            "div(tabindex = 0)[ label(\"x\"); ] {A comment}",
+            "{A comment} label(\"x\");",
+        ] {
+            assert!(!expand(source).contains("compile_error"), "{}", source);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! `azd`, for working with documents without opening a window:
+//!
+//! ```text
+//! azd check <files>...                 report every problem in the documents
+//! azd fmt [--check] [--no-comments] <files>...
+//!                                      rewrite the documents in their canonical form
+//! azd dump tokens|ast <file>           print the tokens or the syntax tree as JSON
+//! azd compile <file> <output>          precompile the document for `load_compiled`
+//! ```
+//!
+//! `dump` is only there with the `serde` feature.
+
+use azul_document_loader_syntax::error::LoadError;
+use azul_document_loader_syntax::load::{load_document, load_into_string};
+use azul_document_loader_syntax::{compiled, print};
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "usage:
+    azd check <files>...
+    azd fmt [--check] [--no-comments] <files>...
+    azd dump tokens|ast <file>
+    azd compile <file> <output>";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let ok = match args.first().map(|x| &**x) {
+        Some("check") if args.len() > 1 => check(&args[1..]),
+        Some("fmt") => fmt(&args[1..]),
+        Some("dump") if args.len() == 3 => dump(&args[1], &args[2]),
+        Some("compile") if args.len() == 3 => compile(&args[1], &args[2]),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    if !ok {
+        process::exit(1);
+    }
+}
+
+/// Prints an error about `file`, quoting it where possible.
+fn report(file: &str, error: &LoadError) {
+    match load_into_string(file) {
+        Ok(source) => eprint!("{}", error.render(&source)),
+        Err(_) => eprintln!("error: {}", error),
+    }
+}
+
+fn check(files: &[String]) -> bool {
+    let mut ok = true;
+    for file in files {
+        if let Err(e) = load_document(file) {
+            report(file, &e);
+            ok = false;
+        }
+    }
+    ok
+}
+
+fn fmt(args: &[String]) -> bool {
+    let check_only = args.iter().any(|x| x == "--check");
+    let keep_comments = !args.iter().any(|x| x == "--no-comments");
+    let files: Vec<&String> = args.iter().filter(|x| !x.starts_with("--")).collect();
+    if files.is_empty() {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+
+    let mut ok = true;
+    for file in files {
+        let source = match load_into_string(file) {
+            Ok(x) => x,
+            Err(e) => {
+                eprintln!("error: {}: {}", file, e);
+                ok = false;
+                continue;
+            }
+        };
+        let formatted = match print::format(&source, file, keep_comments) {
+            Ok(x) => x,
+            Err(e) => {
+                eprint!("{}", e.render(&source));
+                ok = false;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if check_only {
+            eprintln!("{} isn't formatted", file);
+            ok = false;
+        } else if let Err(e) = fs::write(file, formatted) {
+            eprintln!("error: could not write {}: {}", file, e);
+            ok = false;
+        }
+    }
+    ok
+}
+
+/// Prints the tokens or the tree of `file` with serde, so that `azd dump ast` is the
+/// same JSON `load::load_from_json` reads.
+#[cfg(feature = "serde")]
+fn dump(what: &str, file: &str) -> bool {
+    use azul_document_loader_syntax::load::Encoded;
+    use azul_document_loader_syntax::tokenize::tokenize;
+
+    let json = match what {
+        "tokens" => load_into_string(file)
+            .and_then(|source| tokenize(&source, file))
+            .map(|x| serde_json::to_string(&x)),
+        "ast" => {
+            load_document(file).map(|(texts, ast)| serde_json::to_string(&Encoded { texts, ast }))
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    match json {
+        Ok(Ok(x)) => {
+            println!("{}", x);
+            true
+        }
+        Ok(Err(e)) => {
+            eprintln!("error: could not write {} as JSON: {}", file, e);
+            false
+        }
+        Err(e) => {
+            report(file, &e);
+            false
+        }
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+fn dump(_: &str, _: &str) -> bool {
+    eprintln!("error: azd was built without the `serde` feature, which `dump` needs");
+    false
+}
+
+fn compile(file: &str, output: &str) -> bool {
+    let (texts, ast) = match load_document(file) {
+        Ok(x) => x,
+        Err(e) => {
+            report(file, &e);
+            return false;
+        }
+    };
+    match fs::write(output, compiled::compile(&texts, &ast)) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("error: could not write {}: {}", output, e);
+            false
+        }
+    }
+}
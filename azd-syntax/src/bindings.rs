@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+/// A record in a list, with its fields by name.
+pub type Record = HashMap<String, String>;
+
+/// The values a document can refer to from its strings: `label("Count: {counter}")`,
+/// its conditions: `if(logged_in)[ ... ]`, and its loops: `for(user in users)[ ... ]`.
+///
+/// This is meant to be implemented by the app's data model so that every call to
+/// `layout` shows its current state.
+pub trait Bindings {
+    fn get(&self, name: &str) -> Option<String>;
+
+    /// Whether `if(name)` holds: by default when `name` is bound to anything
+    /// other than `""`, `"false"` or `"0"`.
+    fn is_true(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|x| is_truthy(&x))
+    }
+
+    /// The records `for(item in name)` repeats its children for.
+    /// Inside of the loop, `{item.field}` refers to a field of the current record.
+    fn list(&self, name: &str) -> Option<Vec<Record>> {
+        let _ = name;
+        None
+    }
+}
+
+impl Bindings for HashMap<String, String> {
+    fn get(&self, name: &str) -> Option<String> {
+        HashMap::get(self, name).cloned()
+    }
+}
+
+/// Plain values and lists of records, for when the data model doesn't implement `Bindings`.
+#[derive(Debug, Default, Clone)]
+pub struct BindingContext {
+    pub values: HashMap<String, String>,
+    pub lists: HashMap<String, Vec<Record>>,
+}
+
+impl BindingContext {
+    pub fn new() -> BindingContext {
+        BindingContext::default()
+    }
+
+    pub fn with_value<S: Into<String>, V: Into<String>>(mut self, name: S, value: V) -> Self {
+        self.values.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn with_list<S: Into<String>>(mut self, name: S, records: Vec<Record>) -> Self {
+        self.lists.insert(name.into(), records);
+        self
+    }
+}
+
+impl Bindings for BindingContext {
+    fn get(&self, name: &str) -> Option<String> {
+        self.values.get(name).cloned()
+    }
+
+    fn list(&self, name: &str) -> Option<Vec<Record>> {
+        self.lists.get(name).cloned()
+    }
+}
+
+/// The bindings inside of a `for` loop: the current record's fields as `item.field`,
+/// and everything from outside of the loop.
+pub struct Scope<'a> {
+    pub parent: &'a dyn Bindings,
+    pub item: &'a str,
+    pub record: &'a Record,
+}
+
+impl<'a> Scope<'a> {
+    /// The field `name` refers to, if it is `item.field`.
+    fn field(&self, name: &str) -> Option<&'a String> {
+        let field = name.strip_prefix(self.item)?.strip_prefix('.')?;
+        self.record.get(field)
+    }
+}
+
+impl<'a> Bindings for Scope<'a> {
+    fn get(&self, name: &str) -> Option<String> {
+        match self.field(name) {
+            Some(x) => Some(x.clone()),
+            None => self.parent.get(name),
+        }
+    }
+
+    fn is_true(&self, name: &str) -> bool {
+        match self.field(name) {
+            Some(x) => is_truthy(x),
+            None => self.parent.is_true(name),
+        }
+    }
+
+    fn list(&self, name: &str) -> Option<Vec<Record>> {
+        self.parent.list(name)
+    }
+}
+
+/// No bindings at all, for documents which don't use them.
+impl Bindings for () {
+    fn get(&self, _: &str) -> Option<String> {
+        None
+    }
+}
+
+fn is_truthy(value: &str) -> bool {
+    !(value.is_empty() || value == "false" || value == "0")
+}
+
+/// What to do about a template which doesn't work.
+pub const TEMPLATE_HELP: &str =
+    "`{name}` is filled in from the bindings, write `{{` and `}}` for braces";
+
+/// Binds every name, so that filling a template in only fails when it is malformed.
+struct Anything;
+
+impl Bindings for Anything {
+    fn get(&self, _: &str) -> Option<String> {
+        Some(String::new())
+    }
+}
+
+/// Checks that every `{` in `template` is closed around a name, without filling anything in.
+pub fn check_template(template: &str) -> Result<(), String> {
+    interpolate(template, &Anything).map(|_| ())
+}
+
+/// Whether `template` refers to any bindings, and so has to be filled in at layout time.
+pub fn has_bindings(template: &str) -> bool {
+    template.replace("{{", "").contains('{')
+}
+
+/// Replaces every `{name}` in `template` by its value. `{{` and `}}` are literal braces.
+/// Fails with a description of the problem when a name isn't bound or a brace isn't closed.
+pub fn interpolate(template: &str, bindings: &dyn Bindings) -> Result<String, String> {
+    let mut output = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(x) => name.push(x),
+                        None => return Err(format!("Unclosed `{{{}`", name)),
+                    }
+                }
+                let name = name.trim();
+                if name.is_empty() {
+                    return Err("`{}` doesn't name a binding".to_string());
+                }
+                match bindings.get(name) {
+                    Some(x) => output += &x,
+                    None => return Err(format!("Nothing is bound to `{}`", name)),
+                }
+            }
+            x => output.push(x),
+        }
+    }
+    Ok(output)
+}
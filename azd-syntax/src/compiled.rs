@@ -0,0 +1,539 @@
+//! Documents compiled ahead of time into a compact binary form, so that large layouts
+//! don't have to be tokenized and parsed when the app starts.
+//!
+//! A compiled document starts with a header:
+//!
+//! ```text
+//! b"AZDC"     magic
+//! u16         format version, little endian
+//! u64         FNV-1a hash of everything after it, little endian
+//! ```
+//!
+//! followed by every string in the document, interned, then the texts, then the tree
+//! flattened in pre-order with the number of children after each point. Counts are
+//! written as LEB128, strings as their index in the string table, and the numbers of
+//! the document as a little endian `f32` followed by their unit.
+
+use crate::error::LoadError;
+use crate::parse::{
+    ASTPoint, Condition, Declaration, Include, Loop, NType, Node, TextArgument, Use, Value,
+    MAX_DEPTH,
+};
+use crate::span::{Span, Spanned};
+use crate::tokenize::{Number, Unit};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+const MAGIC: &[u8; 4] = b"AZDC";
+
+/// Bumped whenever the layout below changes, so that stale documents are recompiled.
+pub const FORMAT_VERSION: u16 = 1;
+
+const HEADER: usize = 4 + 2 + 8;
+
+fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, x| {
+        (hash ^ u64::from(*x)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Writes a document, interning its strings as they come up.
+#[derive(Default)]
+struct Writer {
+    strings: Vec<String>,
+    interned: HashMap<String, usize>,
+    output: Vec<u8>,
+}
+
+fn write_number(output: &mut Vec<u8>, mut x: usize) {
+    loop {
+        let byte = (x & 0x7f) as u8;
+        x >>= 7;
+        if x == 0 {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+impl Writer {
+    fn number(&mut self, x: usize) {
+        write_number(&mut self.output, x);
+    }
+
+    fn string(&mut self, x: &str) {
+        let index = match self.interned.get(x) {
+            Some(x) => *x,
+            None => {
+                self.strings.push(x.to_string());
+                self.interned.insert(x.to_string(), self.strings.len() - 1);
+                self.strings.len() - 1
+            }
+        };
+        self.number(index);
+    }
+
+    /// Options are a flag, followed by the value if there is one.
+    fn option_string(&mut self, x: &Option<String>) {
+        match x {
+            Some(x) => {
+                self.output.push(1);
+                self.string(x);
+            }
+            None => self.output.push(0),
+        }
+    }
+
+    fn literal(&mut self, x: Number) {
+        self.output.extend_from_slice(&x.value.to_le_bytes());
+        self.output.push(match x.unit {
+            None => 0,
+            Some(Unit::Px) => 1,
+            Some(Unit::Pt) => 2,
+            Some(Unit::Em) => 3,
+            Some(Unit::Percent) => 4,
+        });
+    }
+
+    fn option_literal(&mut self, x: Option<Number>) {
+        match x {
+            Some(x) => {
+                self.output.push(1);
+                self.literal(x);
+            }
+            None => self.output.push(0),
+        }
+    }
+
+    fn span(&mut self, span: &Span) {
+        self.string(&span.file);
+        self.number(span.line);
+        self.number(span.column);
+        self.number(span.start);
+        self.number(span.end);
+    }
+
+    fn text(&mut self, text: &TextArgument) {
+        self.string(&text.body);
+        self.option_string(&text.font);
+        self.option_literal(text.size);
+    }
+
+    fn value(&mut self, value: &Spanned<Value>) {
+        match value.node {
+            Value::Str(ref x) => {
+                self.output.push(0);
+                self.string(x);
+            }
+            Value::Num(x) => {
+                self.output.push(1);
+                self.literal(x);
+            }
+            Value::Bool(x) => {
+                self.output.push(2);
+                self.output.push(x as u8);
+            }
+            Value::Ident(ref x) => {
+                self.output.push(3);
+                self.string(x);
+            }
+        }
+        self.span(&value.span);
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node.ntype {
+            NType::Div => self.output.push(0),
+            NType::Label(ref x) => {
+                self.output.push(1);
+                self.string(x);
+            }
+            NType::Image(ref x) => {
+                self.output.push(2);
+                self.string(x);
+            }
+            NType::Text(x) => {
+                self.output.push(3);
+                self.number(x);
+            }
+            NType::BoundText(ref x) => {
+                self.output.push(4);
+                self.text(x);
+            }
+        }
+        self.option_string(&node.id);
+        self.number(node.classes.len());
+        for x in &node.classes {
+            self.string(x);
+        }
+        self.number(node.attributes.len());
+        for (key, value) in &node.attributes {
+            self.string(key);
+            self.value(value);
+        }
+        self.number(node.style.len());
+        for x in &node.style {
+            self.string(&x.node.property);
+            self.string(&x.node.value);
+            self.span(&x.span);
+        }
+        self.span(&node.span);
+    }
+
+    fn point(&mut self, point: &ASTPoint) {
+        let body = match point {
+            ASTPoint::Element(x) => {
+                self.output.push(0);
+                self.node(x);
+                &[][..]
+            }
+            ASTPoint::Joint(x, body) => {
+                self.output.push(1);
+                self.node(x);
+                &body[..]
+            }
+            ASTPoint::If(x, body) => {
+                self.output.push(2);
+                self.string(&x.name);
+                self.output.push(x.negated as u8);
+                self.span(&x.span);
+                &body[..]
+            }
+            ASTPoint::For(x, body) => {
+                self.output.push(3);
+                self.string(&x.item);
+                self.string(&x.list);
+                self.span(&x.span);
+                &body[..]
+            }
+            ASTPoint::Use(x) => {
+                self.output.push(4);
+                self.string(&x.name);
+                self.number(x.arguments.len());
+                for x in &x.arguments {
+                    self.value(x);
+                }
+                self.span(&x.span);
+                &[][..]
+            }
+            ASTPoint::Include(x) => {
+                self.output.push(5);
+                self.string(&x.path);
+                self.span(&x.span);
+                &[][..]
+            }
+        };
+        self.number(body.len());
+        for x in body {
+            self.point(x);
+        }
+    }
+}
+
+/// Compiles a loaded document, such as one from `load_resources`, for `load_compiled`.
+pub fn compile(texts: &[TextArgument], ast: &ASTPoint) -> Vec<u8> {
+    let mut writer = Writer::default();
+    writer.number(texts.len());
+    for x in texts {
+        writer.text(x);
+    }
+    writer.point(ast);
+
+    let mut payload = Vec::new();
+    write_number(&mut payload, writer.strings.len());
+    for x in &writer.strings {
+        write_number(&mut payload, x.len());
+        payload.extend_from_slice(x.as_bytes());
+    }
+    payload.extend_from_slice(&writer.output);
+
+    let mut output = Vec::with_capacity(HEADER + payload.len());
+    output.extend_from_slice(MAGIC);
+    output.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    output.extend_from_slice(&hash(&payload).to_le_bytes());
+    output.extend_from_slice(&payload);
+    output
+}
+
+fn corrupt(message: &str) -> LoadError {
+    LoadError::Decode(format!("the compiled document is corrupt: {}", message))
+}
+
+/// Reads a document back, checking everything it refers to along the way.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    strings: Vec<Arc<str>>,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, LoadError> {
+        let byte = *self
+            .bytes
+            .get(self.position)
+            .ok_or_else(|| corrupt("it ends early"))?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn number(&mut self) -> Result<usize, LoadError> {
+        let mut number = 0usize;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            let bits = usize::from(byte & 0x7f);
+            if shift >= usize::BITS || (bits << shift) >> shift != bits {
+                return Err(corrupt("a number is too large"));
+            }
+            number |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(number);
+            }
+            shift += 7;
+        }
+    }
+
+    /// A length, which can't be more than the bytes left since everything takes at least one.
+    fn count(&mut self) -> Result<usize, LoadError> {
+        let count = self.number()?;
+        if count > self.bytes.len() - self.position {
+            return Err(corrupt("a length is larger than the document"));
+        }
+        Ok(count)
+    }
+
+    fn bool(&mut self) -> Result<bool, LoadError> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(corrupt("a flag is neither 0 nor 1")),
+        }
+    }
+
+    fn shared(&mut self) -> Result<Arc<str>, LoadError> {
+        let index = self.number()?;
+        self.strings
+            .get(index)
+            .cloned()
+            .ok_or_else(|| corrupt("a string isn't in the string table"))
+    }
+
+    fn string(&mut self) -> Result<String, LoadError> {
+        self.shared().map(|x| x.to_string())
+    }
+
+    fn option_string(&mut self) -> Result<Option<String>, LoadError> {
+        Ok(if self.bool()? {
+            Some(self.string()?)
+        } else {
+            None
+        })
+    }
+
+    fn literal(&mut self) -> Result<Number, LoadError> {
+        let mut bytes = [0; 4];
+        for x in &mut bytes {
+            *x = self.byte()?;
+        }
+        let value = f32::from_le_bytes(bytes);
+        if !value.is_finite() {
+            return Err(corrupt("a number isn't finite"));
+        }
+        let unit = match self.byte()? {
+            0 => None,
+            1 => Some(Unit::Px),
+            2 => Some(Unit::Pt),
+            3 => Some(Unit::Em),
+            4 => Some(Unit::Percent),
+            _ => return Err(corrupt("unknown unit")),
+        };
+        Ok(Number { value, unit })
+    }
+
+    fn option_literal(&mut self) -> Result<Option<Number>, LoadError> {
+        Ok(if self.bool()? {
+            Some(self.literal()?)
+        } else {
+            None
+        })
+    }
+
+    fn strings(&mut self) -> Result<(), LoadError> {
+        let count = self.count()?;
+        for _ in 0..count {
+            let length = self.count()?;
+            let bytes = &self.bytes[self.position..self.position + length];
+            let string = std::str::from_utf8(bytes).map_err(|_| corrupt("a string isn't UTF-8"))?;
+            self.strings.push(Arc::from(string));
+            self.position += length;
+        }
+        Ok(())
+    }
+
+    fn span(&mut self) -> Result<Span, LoadError> {
+        Ok(Span {
+            file: self.shared()?,
+            line: self.number()?,
+            column: self.number()?,
+            start: self.number()?,
+            end: self.number()?,
+        })
+    }
+
+    fn text(&mut self) -> Result<TextArgument, LoadError> {
+        Ok(TextArgument {
+            body: self.string()?,
+            font: self.option_string()?,
+            size: self.option_literal()?,
+        })
+    }
+
+    fn value(&mut self) -> Result<Spanned<Value>, LoadError> {
+        let value = match self.byte()? {
+            0 => Value::Str(self.string()?),
+            1 => Value::Num(self.literal()?),
+            2 => Value::Bool(self.bool()?),
+            3 => Value::Ident(self.string()?),
+            _ => return Err(corrupt("unknown kind of value")),
+        };
+        Ok(Spanned::new(value, self.span()?))
+    }
+
+    fn node(&mut self, texts: usize) -> Result<Node, LoadError> {
+        let ntype = match self.byte()? {
+            0 => NType::Div,
+            1 => NType::Label(self.string()?),
+            2 => NType::Image(self.string()?),
+            3 => match self.number()? {
+                x if x < texts => NType::Text(x),
+                _ => return Err(corrupt("a text node refers to a text which doesn't exist")),
+            },
+            4 => NType::BoundText(self.text()?),
+            _ => return Err(corrupt("unknown kind of node")),
+        };
+        let id = self.option_string()?;
+        let count = self.count()?;
+        let classes = (0..count)
+            .map(|_| self.string())
+            .collect::<Result<Vec<String>, LoadError>>()?;
+        let mut attributes = BTreeMap::new();
+        for _ in 0..self.count()? {
+            let key = self.string()?;
+            attributes.insert(key, self.value()?);
+        }
+        let mut style = Vec::new();
+        for _ in 0..self.count()? {
+            let declaration = Declaration {
+                property: self.string()?,
+                value: self.string()?,
+            };
+            style.push(Spanned::new(declaration, self.span()?));
+        }
+        Ok(Node {
+            ntype,
+            id,
+            classes,
+            attributes,
+            style,
+            span: self.span()?,
+        })
+    }
+
+    fn point(&mut self, texts: usize, depth: usize) -> Result<ASTPoint, LoadError> {
+        if depth > MAX_DEPTH {
+            return Err(corrupt("the tree is nested too deeply"));
+        }
+        let kind = self.byte()?;
+        let point = match kind {
+            0 | 1 => ASTPoint::Element(self.node(texts)?),
+            2 => ASTPoint::If(
+                Condition {
+                    name: self.string()?,
+                    negated: self.bool()?,
+                    span: self.span()?,
+                },
+                Vec::new(),
+            ),
+            3 => ASTPoint::For(
+                Loop {
+                    item: self.string()?,
+                    list: self.string()?,
+                    span: self.span()?,
+                },
+                Vec::new(),
+            ),
+            4 => {
+                let name = self.string()?;
+                let count = self.count()?;
+                let arguments = (0..count)
+                    .map(|_| self.value())
+                    .collect::<Result<Vec<Spanned<Value>>, LoadError>>()?;
+                ASTPoint::Use(Use {
+                    name,
+                    arguments,
+                    span: self.span()?,
+                })
+            }
+            5 => ASTPoint::Include(Include {
+                path: self.string()?,
+                span: self.span()?,
+            }),
+            _ => return Err(corrupt("unknown kind of point")),
+        };
+        let count = self.count()?;
+        let body = (0..count)
+            .map(|_| self.point(texts, depth + 1))
+            .collect::<Result<Vec<ASTPoint>, LoadError>>()?;
+        Ok(match (kind, point) {
+            (1, ASTPoint::Element(x)) => ASTPoint::Joint(x, body),
+            (_, ASTPoint::If(x, _)) => ASTPoint::If(x, body),
+            (_, ASTPoint::For(x, _)) => ASTPoint::For(x, body),
+            (_, x) if body.is_empty() => x,
+            _ => return Err(corrupt("a point which can't have children has some")),
+        })
+    }
+}
+
+/// Loads a document which was compiled with `compile`, without tokenizing or parsing it.
+/// Documents compiled by another version of the format, or damaged since, are rejected.
+pub fn load_compiled(bytes: &[u8]) -> Result<(Vec<TextArgument>, ASTPoint), LoadError> {
+    if bytes.len() < HEADER || &bytes[..4] != MAGIC {
+        return Err(LoadError::Decode("not a compiled document".to_string()));
+    }
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if version != FORMAT_VERSION {
+        return Err(LoadError::Decode(format!(
+            "the document was compiled for format version {}, but this is version {}; \
+             compile it again",
+            version, FORMAT_VERSION
+        )));
+    }
+    let mut expected = [0; 8];
+    expected.copy_from_slice(&bytes[6..HEADER]);
+    if hash(&bytes[HEADER..]) != u64::from_le_bytes(expected) {
+        return Err(corrupt("its hash doesn't match"));
+    }
+
+    let mut reader = Reader {
+        bytes,
+        position: HEADER,
+        strings: Vec::new(),
+    };
+    reader.strings()?;
+    let count = reader.count()?;
+    let texts = (0..count)
+        .map(|_| reader.text())
+        .collect::<Result<Vec<TextArgument>, LoadError>>()?;
+    let ast = reader.point(texts.len(), 0)?;
+    if reader.position != bytes.len() {
+        return Err(corrupt("there is more after the tree"));
+    }
+    // Includes were followed before the document was compiled.
+    match ast {
+        ASTPoint::Element(_) | ASTPoint::Joint(..) => Ok((texts, ast)),
+        _ => Err(LoadError::Decode(
+            "the root of a document has to be a single node".into(),
+        )),
+    }
+}
@@ -0,0 +1,90 @@
+use crate::span::Span;
+use std::fmt;
+
+/// A problem found in a document, with where it happened and optionally how to fix it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new<S: Into<String>>(message: S, span: Span) -> Diagnostic {
+        Diagnostic {
+            message: message.into(),
+            span,
+            help: None,
+        }
+    }
+
+    pub fn with_help<S: Into<String>>(mut self, help: S) -> Diagnostic {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Renders the diagnostic as a multi-line report quoting the offending line of `source`,
+    /// which should be the contents of the file the span points into:
+    ///
+    /// ```text
+    /// error: labels need some text
+    ///  --> layout.azd:3:13
+    ///   |
+    /// 3 |     label:c (12);
+    ///   |              ^^
+    ///   |
+    ///   = help: labels take exactly one string: label("abcd")
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        // Spans which were made up rather than read, such as decoded ones, start at 0.
+        let line = self
+            .span
+            .line
+            .checked_sub(1)
+            .and_then(|x| source.lines().nth(x))
+            .unwrap_or("");
+        let number = self.span.line.to_string();
+        let gutter = " ".repeat(number.len());
+
+        // Keep tabs in the padding so the carets line up with the quoted line.
+        let padding: String = line
+            .chars()
+            .take(self.span.column.saturating_sub(1))
+            .map(|x| if x == '\t' { '\t' } else { ' ' })
+            .collect();
+        let underlined = source
+            .get(self.span.start..self.span.end)
+            .unwrap_or("")
+            .lines()
+            .next()
+            .unwrap_or("")
+            .chars()
+            .count();
+
+        let mut output = format!("error: {}\n", self.message);
+        output += &format!("{}--> {}\n", gutter, self.span);
+        output += &format!("{} |\n", gutter);
+        output += &format!("{} | {}\n", number, line);
+        output += &format!(
+            "{} | {}{}\n",
+            gutter,
+            padding,
+            "^".repeat(underlined.max(1))
+        );
+        if let Some(ref help) = self.help {
+            output += &format!("{} |\n", gutter);
+            output += &format!("{} = help: {}\n", gutter, help);
+        }
+        output
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.span, self.message)?;
+        if let Some(ref help) = self.help {
+            write!(f, " (help: {})", help)?;
+        }
+        Ok(())
+    }
+}
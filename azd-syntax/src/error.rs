@@ -0,0 +1,101 @@
+use crate::diagnostics::Diagnostic;
+use crate::span::Span;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong between reading a `.azd` file and building a `Dom` from it.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The document couldn't be read.
+    Io(io::Error),
+    /// The tokenizer found something it doesn't understand.
+    Lex(Diagnostic),
+    /// The tokens don't form a valid document, with every problem the parser found.
+    Parse(Vec<Diagnostic>),
+    /// A font or image couldn't be registered with the app.
+    Resource(String),
+    /// The syntax tree couldn't be turned into a `Dom`.
+    Dom(Diagnostic),
+    /// An `include` couldn't be followed, because the file can't be read or includes itself.
+    Include(Diagnostic),
+    /// Something went wrong in an included document, whose contents are kept for `render`.
+    Included {
+        document: String,
+        from: Span,
+        error: Box<LoadError>,
+    },
+    /// An encoded or compiled document couldn't be read, or doesn't form a valid tree.
+    Decode(String),
+}
+
+impl LoadError {
+    /// The diagnostics behind this error, empty if it doesn't point into the document.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            LoadError::Lex(x) | LoadError::Dom(x) | LoadError::Include(x) => {
+                std::slice::from_ref(x)
+            }
+            LoadError::Parse(x) => x,
+            LoadError::Included { error, .. } => error.diagnostics(),
+            LoadError::Io(_) | LoadError::Resource(_) | LoadError::Decode(_) => &[],
+        }
+    }
+
+    /// Renders the error for a terminal, quoting `source` when the error points into it.
+    /// Errors in included documents quote the document they came from instead.
+    pub fn render(&self, source: &str) -> String {
+        if let LoadError::Included {
+            document,
+            from,
+            error,
+        } = self
+        {
+            return format!("{}note: included from {}\n", error.render(document), from);
+        }
+        match self.diagnostics() {
+            [] => format!("error: {}\n", self),
+            x => x
+                .iter()
+                .map(|x| x.render(source))
+                .collect::<Vec<String>>()
+                .join("\n"),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "could not read document: {}", e),
+            LoadError::Lex(x) => write!(f, "tokenize error: {}", x),
+            LoadError::Parse(x) => {
+                let x: Vec<String> = x.iter().map(|x| x.to_string()).collect();
+                write!(f, "parse error: {}", x.join("; "))
+            }
+            LoadError::Resource(x) => write!(f, "resource error: {}", x),
+            LoadError::Dom(x) => write!(f, "dom error: {}", x),
+            LoadError::Include(x) => write!(f, "include error: {}", x),
+            LoadError::Included { from, error, .. } => {
+                write!(f, "{} (included from {})", error, from)
+            }
+            LoadError::Decode(x) => write!(f, "decode error: {}", x),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(e) => Some(e),
+            LoadError::Included { error, .. } => Some(&**error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> LoadError {
+        LoadError::Io(e)
+    }
+}
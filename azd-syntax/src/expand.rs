@@ -0,0 +1,383 @@
+use crate::bindings::{has_bindings, interpolate};
+use crate::diagnostics::Diagnostic;
+use crate::parse::{
+    ASTPoint, Component, Declaration, Include, NType, Node, TextArgument, Use, Value, MAX_DEPTH,
+};
+use crate::span::{Span, Spanned};
+use std::collections::{HashMap, HashSet};
+
+const COMPONENT_HELP: &str = "components are defined as `component card(title, img)[ ... ]` \
+                              and used as `card(\"Hi\", \"catimg\");`";
+
+/// How many nodes a document can expand to, since components using each other
+/// several times make the tree grow exponentially.
+const MAX_NODES: usize = 1 << 20;
+
+/// The values a component was used with, by the name of their parameter.
+type Arguments<'a> = HashMap<&'a str, String>;
+
+/// Replaces every use of a component in `ast` by the component's body.
+///
+/// The texts of the expanded tree are collected anew, since a text inside of a component
+/// is created once for every time the component is used.
+pub fn expand(
+    ast: &ASTPoint,
+    components: &[Component],
+    texts: &[TextArgument],
+) -> Result<(Vec<TextArgument>, ASTPoint), Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+    let mut by_name: HashMap<&str, &Component> = HashMap::new();
+    for x in components {
+        if by_name.insert(&x.name, x).is_some() {
+            diagnostics.push(Diagnostic::new(
+                format!("The component `{}` is defined more than once", x.name),
+                x.span.clone(),
+            ));
+        }
+    }
+
+    check_uses(std::slice::from_ref(ast), None, &by_name, &mut diagnostics);
+    for x in components {
+        check_uses(&x.body, Some(x), &by_name, &mut diagnostics);
+    }
+    let mut done = HashSet::new();
+    for x in components {
+        check_recursion(x, &by_name, &mut Vec::new(), &mut done, &mut diagnostics);
+    }
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+    measure(std::slice::from_ref(ast), 0, &by_name, &mut HashMap::new()).map_err(|x| vec![x])?;
+
+    let mut expander = Expander {
+        components: by_name,
+        old_texts: texts,
+        texts: Vec::new(),
+    };
+    let mut root = expander.point(ast, &Arguments::new());
+    match (root.pop(), root.is_empty()) {
+        (Some(x @ ASTPoint::Element(..)), true)
+        | (Some(x @ ASTPoint::Joint(..)), true)
+        | (Some(x @ ASTPoint::Include(..)), true) => Ok((expander.texts, x)),
+        _ => Err(vec![Diagnostic::new(
+            "The root of a document has to be a single node",
+            ast.span().clone(),
+        )
+        .with_help(
+            "a component used as the root has to consist of a single node",
+        )]),
+    }
+}
+
+/// Calls `f` for every use of a component in `points`, including inside of other nodes.
+fn for_each_use<'a, F>(points: &'a [ASTPoint], f: &mut F)
+where
+    F: FnMut(&'a Use),
+{
+    for i in points {
+        match i {
+            ASTPoint::Element(_) | ASTPoint::Include(_) => {}
+            ASTPoint::Joint(_, body) | ASTPoint::If(_, body) | ASTPoint::For(_, body) => {
+                for_each_use(body, f)
+            }
+            ASTPoint::Use(x) => f(x),
+        }
+    }
+}
+
+/// Checks that every component used in `points` exists and is given the right arguments.
+/// `parent` is the component `points` belong to, whose parameters can be passed on.
+fn check_uses(
+    points: &[ASTPoint],
+    parent: Option<&Component>,
+    components: &HashMap<&str, &Component>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for_each_use(points, &mut |x: &Use| {
+        let component = match components.get(&*x.name) {
+            Some(x) => x,
+            None => {
+                diagnostics.push(
+                    Diagnostic::new(
+                        format!("There is no component called `{}`", x.name),
+                        x.span.clone(),
+                    )
+                    .with_help(COMPONENT_HELP),
+                );
+                return;
+            }
+        };
+        if component.parameters.len() != x.arguments.len() {
+            diagnostics.push(
+                Diagnostic::new(
+                    format!(
+                        "The component `{}` takes {} argument(s) but was given {}",
+                        x.name,
+                        component.parameters.len(),
+                        x.arguments.len()
+                    ),
+                    x.span.clone(),
+                )
+                .with_help(format!(
+                    "its parameters are ({})",
+                    component.parameters.join(", ")
+                )),
+            );
+        }
+        for argument in &x.arguments {
+            if let Value::Ident(ref name) = argument.node {
+                if !parent.is_some_and(|p| p.parameters.contains(name)) {
+                    diagnostics.push(
+                        Diagnostic::new(
+                            format!("`{}` is not a parameter", name),
+                            argument.span.clone(),
+                        )
+                        .with_help(
+                            "components are given strings, numbers, \
+                                        or the parameters of the component they are used in",
+                        ),
+                    );
+                }
+            }
+        }
+    });
+}
+
+/// Reports every component which ends up using itself, since expanding it would never end.
+fn check_recursion<'a>(
+    component: &'a Component,
+    components: &HashMap<&str, &'a Component>,
+    stack: &mut Vec<&'a str>,
+    done: &mut HashSet<&'a str>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if done.contains(&*component.name) {
+        return;
+    }
+    if stack.len() > MAX_DEPTH {
+        diagnostics.push(
+            Diagnostic::new(
+                format!(
+                    "Components use each other more than {} levels deep",
+                    MAX_DEPTH
+                ),
+                component.span.clone(),
+            )
+            .with_help(format!(
+                "`{}` is used through {}",
+                component.name,
+                stack.join(" -> ")
+            )),
+        );
+        return;
+    }
+    stack.push(&component.name);
+    let mut used = Vec::new();
+    for_each_use(&component.body, &mut |x: &'a Use| used.push(x));
+    for x in used {
+        if let Some(start) = stack.iter().position(|name| *name == x.name) {
+            let mut cycle = stack[start..].to_vec();
+            cycle.push(&x.name);
+            diagnostics.push(
+                Diagnostic::new(
+                    format!("The component `{}` uses itself", x.name),
+                    x.span.clone(),
+                )
+                .with_help(format!(
+                    "components can't be recursive: {}",
+                    cycle.join(" -> ")
+                )),
+            );
+        } else if let Some(next) = components.get(&*x.name) {
+            check_recursion(next, components, stack, done, diagnostics);
+        }
+    }
+    stack.pop();
+    done.insert(&component.name);
+}
+
+/// How many points `points` expand to, and how many levels deeper than `depth` they nest,
+/// remembering both for each component on the way. Expanding stops being tried as soon
+/// as either is too large, and is only tried once the components aren't recursive.
+fn measure<'a>(
+    points: &'a [ASTPoint],
+    depth: usize,
+    components: &HashMap<&str, &'a Component>,
+    measured: &mut HashMap<&'a str, (usize, usize)>,
+) -> Result<(usize, usize), Diagnostic> {
+    let too_deep = |span: &Span| {
+        Diagnostic::new(
+            format!("Nodes are nested more than {} levels deep", MAX_DEPTH),
+            span.clone(),
+        )
+        .with_help("a component's depth is added to the depth it is used at")
+    };
+    let mut total = (0, 0);
+    for x in points {
+        let (size, below) = match x {
+            ASTPoint::Joint(_, body) | ASTPoint::If(_, body) | ASTPoint::For(_, body) => {
+                if depth >= MAX_DEPTH {
+                    return Err(too_deep(x.span()));
+                }
+                let (size, below) = measure(body, depth + 1, components, measured)?;
+                (size + 1, below + 1)
+            }
+            ASTPoint::Use(x) => {
+                let found = match measured.get(&*x.name) {
+                    Some(x) => *x,
+                    None => {
+                        let component = components[&*x.name];
+                        let found = measure(&component.body, depth, components, measured)?;
+                        measured.insert(&component.name, found);
+                        found
+                    }
+                };
+                if depth + found.1 > MAX_DEPTH {
+                    return Err(too_deep(&x.span));
+                }
+                found
+            }
+            ASTPoint::Element(_) | ASTPoint::Include(_) => (1, 0),
+        };
+        total = (total.0 + size, usize::max(total.1, below));
+        if total.0 > MAX_NODES {
+            return Err(Diagnostic::new(
+                format!("The document expands to more than {} nodes", MAX_NODES),
+                x.span().clone(),
+            )
+            .with_help("a component which uses others several times grows with each of them"));
+        }
+    }
+    Ok(total)
+}
+
+/// Replaces every `{parameter}` in `template` by its argument, leaving other braces alone.
+/// Arguments are inserted as they were written, so they can refer to bindings themselves.
+fn substitute(template: &str, arguments: &Arguments) -> String {
+    if arguments.is_empty() {
+        return template.to_string();
+    }
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        output += &rest[..start];
+        rest = &rest[start..];
+        if rest.starts_with("{{") {
+            output += "{{";
+            rest = &rest[2..];
+            continue;
+        }
+        let end = match rest.find('}') {
+            Some(x) => x,
+            None => break,
+        };
+        match arguments.get(rest[1..end].trim()) {
+            Some(x) => output += x,
+            None => output += &rest[..=end],
+        }
+        rest = &rest[end + 1..];
+    }
+    output + rest
+}
+
+struct Expander<'a> {
+    components: HashMap<&'a str, &'a Component>,
+    old_texts: &'a [TextArgument],
+    texts: Vec<TextArgument>,
+}
+
+impl<'a> Expander<'a> {
+    fn node(&mut self, node: &Node, arguments: &Arguments) -> Node {
+        let ntype = match node.ntype {
+            NType::Div => NType::Div,
+            NType::Label(ref x) => NType::Label(substitute(x, arguments)),
+            NType::Image(ref x) => NType::Image(substitute(x, arguments)),
+            NType::Text(index) => {
+                self.texts.push(self.old_texts[index].clone());
+                NType::Text(self.texts.len() - 1)
+            }
+            NType::BoundText(ref x) => {
+                let mut text = x.clone();
+                text.body = substitute(&text.body, arguments);
+                if has_bindings(&text.body) {
+                    NType::BoundText(text)
+                } else {
+                    // Every binding was a parameter, so the text can be cached after all.
+                    if let Ok(x) = interpolate(&text.body, &()) {
+                        text.body = x;
+                    }
+                    self.texts.push(text);
+                    NType::Text(self.texts.len() - 1)
+                }
+            }
+        };
+        let attributes = node
+            .attributes
+            .iter()
+            .map(|(key, value)| {
+                let node = match value.node {
+                    Value::Str(ref x) => Value::Str(substitute(x, arguments)),
+                    ref x => x.clone(),
+                };
+                (key.clone(), Spanned::new(node, value.span.clone()))
+            })
+            .collect();
+        let style = node
+            .style
+            .iter()
+            .map(|x| {
+                let declaration = Declaration {
+                    value: substitute(&x.node.value, arguments),
+                    ..x.node.clone()
+                };
+                Spanned::new(declaration, x.span.clone())
+            })
+            .collect();
+        Node {
+            ntype,
+            attributes,
+            style,
+            ..node.clone()
+        }
+    }
+
+    fn body(&mut self, body: &[ASTPoint], arguments: &Arguments) -> Vec<ASTPoint> {
+        body.iter().flat_map(|x| self.point(x, arguments)).collect()
+    }
+
+    /// The points `point` expands to: more than one when it is a use of a component.
+    fn point(&mut self, point: &ASTPoint, arguments: &Arguments) -> Vec<ASTPoint> {
+        let expanded = match point {
+            ASTPoint::Element(x) => ASTPoint::Element(self.node(x, arguments)),
+            ASTPoint::Joint(x, body) => {
+                let node = self.node(x, arguments);
+                ASTPoint::Joint(node, self.body(body, arguments))
+            }
+            ASTPoint::If(x, body) => ASTPoint::If(x.clone(), self.body(body, arguments)),
+            ASTPoint::For(x, body) => ASTPoint::For(x.clone(), self.body(body, arguments)),
+            ASTPoint::Include(x) => ASTPoint::Include(Include {
+                path: substitute(&x.path, arguments),
+                span: x.span.clone(),
+            }),
+            ASTPoint::Use(x) => {
+                let component = self.components[&*x.name];
+                let given = component
+                    .parameters
+                    .iter()
+                    .zip(&x.arguments)
+                    .map(|(name, value)| {
+                        let value = match value.node {
+                            Value::Str(ref x) => substitute(x, arguments),
+                            Value::Num(x) => x.to_string(),
+                            Value::Bool(x) => x.to_string(),
+                            Value::Ident(ref x) => arguments[&**x].clone(),
+                        };
+                        (&**name, value)
+                    })
+                    .collect();
+                return self.body(&component.body, &given);
+            }
+        };
+        vec![expanded]
+    }
+}
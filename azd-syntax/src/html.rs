@@ -0,0 +1,576 @@
+//! Loads mockups written in a subset of XHTML into the same tree as `.azd` documents:
+//!
+//! ```text
+//! <div id="root" class="a b">   div#root.a.b[ ... ]
+//! <p>Some text</p>              text("Some text");
+//! <span>Some text</span>        label("Some text");
+//! <img src="logo.png"/>         image("logo.png");
+//! ```
+//!
+//! A document may be wrapped in `<html>` and `<body>`, and its `<head>` is skipped.
+//! Every other tag or attribute is an error, rather than being left out of the layout.
+
+use crate::diagnostics::Diagnostic;
+use crate::error::LoadError;
+use crate::parse::{ASTPoint, NType, Node, TextArgument, MAX_DEPTH};
+use crate::span::Span;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+const TAGS_HELP: &str = "the supported tags are `<div>`, `<p>`, `<span>` and `<img>`";
+
+/// Turns char indices into spans with byte offsets, lines and columns.
+struct Locator {
+    file: Arc<str>,
+    offsets: Vec<usize>,
+    line_starts: Vec<usize>,
+}
+
+impl Locator {
+    fn new(file: &str, source: &str) -> Locator {
+        let mut offsets: Vec<usize> = source.char_indices().map(|(x, _)| x).collect();
+        offsets.push(source.len());
+        let mut line_starts = vec![0];
+        for (index, c) in source.chars().enumerate() {
+            if c == '\n' {
+                line_starts.push(index + 1);
+            }
+        }
+        Locator {
+            file: file.into(),
+            offsets,
+            line_starts,
+        }
+    }
+
+    /// The span covering the chars `start..end`.
+    fn span(&self, start: usize, end: usize) -> Span {
+        let line = match self.line_starts.binary_search(&start) {
+            Ok(x) => x,
+            Err(x) => x - 1,
+        };
+        Span {
+            file: self.file.clone(),
+            line: line + 1,
+            column: start - self.line_starts[line] + 1,
+            start: self.offsets[start],
+            end: self.offsets[end],
+        }
+    }
+}
+
+/// What an element contains, before it is checked against the subset.
+enum Content {
+    Element(Element),
+    Text(String, Span),
+}
+
+struct Element {
+    name: String,
+    attributes: Vec<(String, String, Span)>,
+    children: Vec<Content>,
+    /// The start tag.
+    span: Span,
+}
+
+/// Reads the markup into elements, knowing nothing about which ones are supported.
+struct Reader<'a> {
+    source: Vec<char>,
+    index: usize,
+    locator: &'a Locator,
+    /// How many elements the reader is inside of.
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn starts_with(&self, prefix: &str) -> bool {
+        prefix
+            .chars()
+            .enumerate()
+            .all(|(i, x)| self.source.get(self.index + i) == Some(&x))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source.get(self.index).cloned()
+    }
+
+    fn error(&self, start: usize, message: String, help: &str) -> Diagnostic {
+        let end = (start + 1).min(self.source.len());
+        Diagnostic::new(message, self.locator.span(start, end)).with_help(help)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|x| x.is_ascii_whitespace()) {
+            self.index += 1;
+        }
+    }
+
+    /// Skips ahead to just past `end`.
+    fn skip_past(&mut self, end: &str, what: &str) -> Result<(), Diagnostic> {
+        let start = self.index;
+        while !self.starts_with(end) {
+            if self.index >= self.source.len() {
+                return Err(self.error(
+                    start,
+                    format!("Unclosed {}", what),
+                    &format!("it ends with `{}`", end),
+                ));
+            }
+            self.index += 1;
+        }
+        self.index += end.chars().count();
+        Ok(())
+    }
+
+    fn name(&mut self) -> String {
+        let start = self.index;
+        while self
+            .peek()
+            .is_some_and(|x| x.is_alphanumeric() || x == '-' || x == '_' || x == ':')
+        {
+            self.index += 1;
+        }
+        self.source[start..self.index]
+            .iter()
+            .collect::<String>()
+            .to_lowercase()
+    }
+
+    /// Decodes the entity at `&`.
+    fn entity(&mut self) -> Result<char, Diagnostic> {
+        let start = self.index;
+        self.index += 1;
+        let name: String = self.name();
+        let name = if name.is_empty() && self.peek() == Some('#') {
+            self.index += 1;
+            format!("#{}", self.name())
+        } else {
+            name
+        };
+        let decoded = match &*name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            x if x.starts_with("#x") => u32::from_str_radix(&x[2..], 16)
+                .ok()
+                .and_then(std::char::from_u32),
+            x if x.starts_with('#') => x[1..].parse().ok().and_then(std::char::from_u32),
+            _ => None,
+        };
+        match (decoded, self.peek()) {
+            (Some(x), Some(';')) => {
+                self.index += 1;
+                Ok(x)
+            }
+            _ => Err(self.error(
+                start,
+                format!("Unknown entity `&{}`", name),
+                "write `&amp;` for a literal `&`",
+            )),
+        }
+    }
+
+    fn text(&mut self) -> Result<Content, Diagnostic> {
+        let start = self.index;
+        let mut text = String::new();
+        while let Some(x) = self.peek() {
+            match x {
+                '<' => break,
+                '&' => text.push(self.entity()?),
+                x => {
+                    text.push(x);
+                    self.index += 1;
+                }
+            }
+        }
+        Ok(Content::Text(text, self.locator.span(start, self.index)))
+    }
+
+    fn value(&mut self) -> Result<String, Diagnostic> {
+        let start = self.index;
+        let quote = match self.peek() {
+            Some(x) if x == '"' || x == '\'' => x,
+            _ => {
+                return Err(self.error(
+                    start,
+                    "Expected a quoted value".to_string(),
+                    "attributes are written as `name=\"value\"`",
+                ))
+            }
+        };
+        self.index += 1;
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                None => {
+                    return Err(self.error(
+                        start,
+                        "Unclosed attribute value".to_string(),
+                        "attributes are written as `name=\"value\"`",
+                    ))
+                }
+                Some(x) if x == quote => break,
+                Some('&') => value.push(self.entity()?),
+                Some(x) => {
+                    value.push(x);
+                    self.index += 1;
+                }
+            }
+        }
+        self.index += 1;
+        Ok(value)
+    }
+
+    /// Reads the element whose start tag begins at `<`.
+    fn element(&mut self) -> Result<Element, Diagnostic> {
+        let start = self.index;
+        self.index += 1;
+        let name = self.name();
+        if name.is_empty() {
+            return Err(self.error(
+                start,
+                "Expected a tag name after `<`".to_string(),
+                "write `&lt;` for a literal `<`",
+            ));
+        }
+        let mut attributes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let attribute = self.index;
+            match self.peek() {
+                Some('>') => {
+                    self.index += 1;
+                    break;
+                }
+                Some('/') if self.starts_with("/>") => {
+                    self.index += 2;
+                    let span = self.locator.span(start, self.index);
+                    return Ok(Element {
+                        name,
+                        attributes,
+                        children: Vec::new(),
+                        span,
+                    });
+                }
+                None => {
+                    return Err(self.error(
+                        start,
+                        format!("Unclosed tag `<{}`", name),
+                        "tags end with `>`, or `/>` if they have no contents",
+                    ))
+                }
+                _ => {}
+            }
+            let key = self.name();
+            if key.is_empty() {
+                return Err(self.error(
+                    attribute,
+                    format!("Unexpected `{}` in `<{}>`", self.source[attribute], name),
+                    "attributes are written as `name=\"value\"`",
+                ));
+            }
+            self.skip_whitespace();
+            let value = if self.peek() == Some('=') {
+                self.index += 1;
+                self.skip_whitespace();
+                self.value()?
+            } else {
+                String::new()
+            };
+            attributes.push((key, value, self.locator.span(attribute, self.index)));
+        }
+        let span = self.locator.span(start, self.index);
+        if self.depth >= MAX_DEPTH {
+            return Err(Diagnostic::new(
+                format!("Elements are nested more than {} levels deep", MAX_DEPTH),
+                span,
+            ));
+        }
+        self.depth += 1;
+        let children = self.contents(Some((&name, &span)))?;
+        self.depth -= 1;
+        Ok(Element {
+            name,
+            attributes,
+            children,
+            span,
+        })
+    }
+
+    /// Reads everything up to the end tag of `parent`, or up to the end of the document.
+    fn contents(&mut self, parent: Option<(&str, &Span)>) -> Result<Vec<Content>, Diagnostic> {
+        let mut contents = Vec::new();
+        loop {
+            if self.index >= self.source.len() {
+                return match parent {
+                    Some((name, span)) => Err(Diagnostic::new(
+                        format!("`<{}>` is never closed", name),
+                        span.clone(),
+                    )
+                    .with_help(format!(
+                        "elements end with `</{}>`, or are written as `<{} />`",
+                        name, name
+                    ))),
+                    None => Ok(contents),
+                };
+            }
+            if self.starts_with("<!--") {
+                self.skip_past("-->", "comment")?;
+            } else if self.starts_with("<!") || self.starts_with("<?") {
+                // Doctypes and the XML declaration say nothing about the layout.
+                self.skip_past(">", "declaration")?;
+            } else if self.starts_with("</") {
+                let start = self.index;
+                self.index += 2;
+                let name = self.name();
+                self.skip_whitespace();
+                if self.peek() != Some('>') {
+                    return Err(self.error(
+                        start,
+                        format!("Unclosed tag `</{}`", name),
+                        "end tags end with `>`",
+                    ));
+                }
+                self.index += 1;
+                return match parent {
+                    Some((x, _)) if x == name => Ok(contents),
+                    Some((x, _)) => Err(Diagnostic::new(
+                        format!("Expected `</{}>`, found `</{}>`", x, name),
+                        self.locator.span(start, self.index),
+                    )
+                    .with_help("elements have to be closed in the order they were opened")),
+                    None => Err(Diagnostic::new(
+                        format!("`</{}>` doesn't close anything", name),
+                        self.locator.span(start, self.index),
+                    )),
+                };
+            } else if self.peek() == Some('<') {
+                contents.push(Content::Element(self.element()?));
+            } else {
+                contents.push(self.text()?);
+            }
+        }
+    }
+}
+
+/// Collapses whitespace like a browser would.
+fn collapse(text: &str) -> String {
+    text.split_ascii_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Checks elements against the subset and turns them into syntax tree nodes,
+/// collecting every problem on the way.
+struct Converter {
+    texts: Vec<TextArgument>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Converter {
+    fn node(&mut self, element: &Element, ntype: NType, allowed: &[&str]) -> Node {
+        let mut node = Node {
+            ntype,
+            id: None,
+            classes: Vec::new(),
+            attributes: BTreeMap::new(),
+            style: Vec::new(),
+            span: element.span.clone(),
+        };
+        for (key, value, span) in &element.attributes {
+            match &**key {
+                "id" => node.id = Some(value.clone()),
+                "class" => {
+                    node.classes = value.split_ascii_whitespace().map(String::from).collect()
+                }
+                x if allowed.contains(&x) => {}
+                x => {
+                    let mut names: Vec<String> = vec!["`id`".to_string(), "`class`".to_string()];
+                    names.extend(allowed.iter().map(|x| format!("`{}`", x)));
+                    self.diagnostics.push(
+                        Diagnostic::new(
+                            format!("Unsupported attribute `{}` on `<{}>`", x, element.name),
+                            span.clone(),
+                        )
+                        .with_help(format!(
+                            "`<{}>` only takes {}",
+                            element.name,
+                            names.join(", ")
+                        )),
+                    );
+                }
+            }
+        }
+        node
+    }
+
+    /// The text of a `<p>` or `<span>`, which can't contain other elements.
+    fn text(&mut self, element: &Element) -> String {
+        let mut text = String::new();
+        for x in &element.children {
+            match x {
+                Content::Text(x, _) => text += x,
+                Content::Element(x) => self.diagnostics.push(
+                    Diagnostic::new(
+                        format!("`<{}>` can only contain text", element.name),
+                        x.span.clone(),
+                    )
+                    .with_help("put the elements next to each other in a `<div>` instead"),
+                ),
+            }
+        }
+        collapse(&text)
+    }
+
+    fn element(&mut self, element: &Element) -> Option<ASTPoint> {
+        match &*element.name {
+            "div" => {
+                let node = self.node(element, NType::Div, &[]);
+                let body = self.body(&element.children);
+                Some(if body.is_empty() {
+                    ASTPoint::Element(node)
+                } else {
+                    ASTPoint::Joint(node, body)
+                })
+            }
+            "p" => {
+                let text = self.text(element);
+                self.texts.push(TextArgument {
+                    body: text,
+                    font: None,
+                    size: None,
+                });
+                let ntype = NType::Text(self.texts.len() - 1);
+                Some(ASTPoint::Element(self.node(element, ntype, &[])))
+            }
+            "span" => {
+                // Labels are filled in from the bindings, which HTML knows nothing about.
+                let text = self.text(element).replace('{', "{{").replace('}', "}}");
+                Some(ASTPoint::Element(self.node(
+                    element,
+                    NType::Label(text),
+                    &[],
+                )))
+            }
+            "img" => {
+                if let Some(x) = element.children.iter().find(|x| match x {
+                    Content::Text(x, _) => !collapse(x).is_empty(),
+                    Content::Element(_) => true,
+                }) {
+                    let span = match x {
+                        Content::Text(_, x) => x,
+                        Content::Element(x) => &x.span,
+                    };
+                    self.diagnostics.push(
+                        Diagnostic::new("`<img>` can't have contents", span.clone())
+                            .with_help("write `<img src=\"...\" />`"),
+                    );
+                }
+                let source = element
+                    .attributes
+                    .iter()
+                    .find(|x| x.0 == "src")
+                    .map(|x| x.1.clone());
+                if source.is_none() {
+                    self.diagnostics.push(
+                        Diagnostic::new("`<img>` needs a `src`", element.span.clone()).with_help(
+                            "the source is the name the image was loaded under: \
+                             `<img src=\"logo.png\" />`",
+                        ),
+                    );
+                }
+                let ntype = NType::Image(source.unwrap_or_default());
+                Some(ASTPoint::Element(self.node(element, ntype, &["src"])))
+            }
+            x => {
+                self.diagnostics.push(
+                    Diagnostic::new(format!("Unsupported tag `<{}>`", x), element.span.clone())
+                        .with_help(TAGS_HELP),
+                );
+                None
+            }
+        }
+    }
+
+    fn body(&mut self, contents: &[Content]) -> Vec<ASTPoint> {
+        let mut body = Vec::new();
+        for x in contents {
+            match x {
+                Content::Element(x) => body.extend(self.element(x)),
+                Content::Text(x, span) if !collapse(x).is_empty() => self.diagnostics.push(
+                    Diagnostic::new(
+                        "Text has to be inside of a `<p>` or a `<span>`",
+                        span.clone(),
+                    )
+                    .with_help("`<p>` is laid out as a text, and `<span>` as a label"),
+                ),
+                Content::Text(..) => {}
+            }
+        }
+        body
+    }
+}
+
+/// The elements which make up the document itself, inside of `<html>` and `<body>`.
+fn unwrap(contents: Vec<Content>) -> Vec<Content> {
+    let mut elements = contents.into_iter().filter(|x| match x {
+        Content::Text(x, _) => !collapse(x).is_empty(),
+        Content::Element(_) => true,
+    });
+    match (elements.next(), elements.next()) {
+        (Some(Content::Element(x)), None) if x.name == "html" => x
+            .children
+            .into_iter()
+            .filter(|x| !matches!(x, Content::Element(x) if x.name == "head"))
+            .flat_map(|x| match x {
+                Content::Element(x) if x.name == "body" => x.children,
+                x => vec![x],
+            })
+            .collect(),
+        (Some(Content::Element(x)), None) if x.name == "body" => x.children,
+        (first, second) => first.into_iter().chain(second).chain(elements).collect(),
+    }
+}
+
+/// Reads an XHTML document into the same texts and tree `load_document` returns.
+/// `file` is only used to label the spans.
+pub fn parse(source: &str, file: &str) -> Result<(Vec<TextArgument>, ASTPoint), LoadError> {
+    let locator = Locator::new(file, source);
+    let mut reader = Reader {
+        source: source.chars().collect(),
+        index: 0,
+        depth: 0,
+        locator: &locator,
+    };
+    let contents = reader
+        .contents(None)
+        .map_err(|x| LoadError::Parse(vec![x]))?;
+    let mut converter = Converter {
+        texts: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    let mut roots = converter.body(&unwrap(contents));
+    if roots.len() > 1 {
+        converter.diagnostics.push(
+            Diagnostic::new(
+                "A document only has one root element",
+                roots[1].span().clone(),
+            )
+            .with_help("wrap the elements in a `<div>`"),
+        );
+    }
+    if !converter.diagnostics.is_empty() {
+        return Err(LoadError::Parse(converter.diagnostics));
+    }
+    match roots.pop() {
+        Some(x) => Ok((converter.texts, x)),
+        None => Err(LoadError::Parse(vec![Diagnostic::new(
+            "The document has no root element",
+            locator.span(0, 0),
+        )
+        .with_help(TAGS_HELP)])),
+    }
+}
@@ -0,0 +1,17 @@
+//! The `.azd` language without azul: tokenizing, parsing, expanding components,
+//! following includes, compiling documents and printing them back out.
+//!
+//! `azul-document-loader` builds `Dom`s from what this loads, and `azd!` uses it to
+//! parse documents at compile time, so both read documents the same way.
+
+pub mod bindings;
+pub mod compiled;
+pub mod diagnostics;
+pub mod error;
+pub mod expand;
+pub mod html;
+pub mod load;
+pub mod parse;
+pub mod print;
+pub mod span;
+pub mod tokenize;
@@ -0,0 +1,253 @@
+use crate::diagnostics::Diagnostic;
+use crate::error::LoadError;
+use crate::html;
+use crate::parse::{self, ASTPoint, Include, NType, Node, TextArgument};
+use crate::tokenize::tokenize;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+pub fn load_into_string(filename: &str) -> Result<String, LoadError> {
+    let mut contents = String::new();
+    let mut file = File::open(filename)?;
+    file.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+/// The documents which are being included, outermost first, to find include cycles.
+/// Each is kept as its canonical path to compare, and as it was written to report.
+type Stack = Vec<(PathBuf, String)>;
+
+/// Loads the document at `filename` along with every document it includes.
+/// The texts of all of them are collected into one list.
+/// Files ending in `.html`, `.htm` or `.xhtml` are read by the `html` front-end.
+pub fn load_document(filename: &str) -> Result<(Vec<TextArgument>, ASTPoint), LoadError> {
+    load_named(&load_into_string(filename)?, filename)
+}
+
+/// Loads a document which was already read, as if it came from a file called `name`:
+/// its spans are labelled with `name`, and its includes are found relative to it.
+/// Every other way of loading a document ends up here.
+pub fn load_named(source: &str, name: &str) -> Result<(Vec<TextArgument>, ASTPoint), LoadError> {
+    let mut texts = Vec::new();
+    let ast = load_source(Path::new(name), source, &mut Stack::new(), &mut texts)?;
+    Ok((texts, ast))
+}
+
+/// Loads a document from a string. Its includes are found relative to the working directory.
+pub fn load_from_str(source: &str) -> Result<(Vec<TextArgument>, ASTPoint), LoadError> {
+    load_named(source, "<string>")
+}
+
+/// Loads a document from anything which can be read, such as a socket or an archive.
+/// Its includes are found relative to the working directory.
+pub fn load_from_reader<R: Read>(
+    mut reader: R,
+) -> Result<(Vec<TextArgument>, ASTPoint), LoadError> {
+    let mut source = String::new();
+    reader.read_to_string(&mut source)?;
+    load_named(&source, "<reader>")
+}
+
+/// Loads a document which `include_azd!` embedded in the binary, as if it came from a
+/// file called `name`. Its includes would still be read from disk, so they are an error.
+pub fn load_embedded(source: &str, name: &str) -> Result<(Vec<TextArgument>, ASTPoint), LoadError> {
+    let tokens = tokenize(source, name)?;
+    let (texts, ast) = parse::parse(&mut VecDeque::from(tokens))?;
+    match first_include(&ast) {
+        Some(x) => Err(LoadError::Include(
+            Diagnostic::new(
+                format!("{:?} can't be included by an embedded document", x.path),
+                x.span.clone(),
+            )
+            .with_help("embed the document in one piece, or load it with `load_resources`"),
+        )),
+        None => Ok((texts, ast)),
+    }
+}
+
+fn first_include(point: &ASTPoint) -> Option<&Include> {
+    match point {
+        ASTPoint::Include(x) => Some(x),
+        ASTPoint::Joint(_, body) | ASTPoint::If(_, body) | ASTPoint::For(_, body) => {
+            body.iter().find_map(first_include)
+        }
+        ASTPoint::Element(_) | ASTPoint::Use(_) => None,
+    }
+}
+
+/// A document as other tools encode it: the same texts and tree `load_document` returns.
+/// Spans, ids, classes and attributes may be left out.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Encoded {
+    #[serde(default)]
+    pub texts: Vec<TextArgument>,
+    pub ast: ASTPoint,
+}
+
+/// Loads a document encoded as JSON. Its includes are found relative to the working directory.
+#[cfg(feature = "serde")]
+pub fn load_from_json(source: &str) -> Result<(Vec<TextArgument>, ASTPoint), LoadError> {
+    let encoded = serde_json::from_str(source).map_err(|e| LoadError::Decode(e.to_string()))?;
+    load_encoded(encoded, "<json>")
+}
+
+/// Loads a document encoded as RON. Its includes are found relative to the working directory.
+#[cfg(feature = "serde")]
+pub fn load_from_ron(source: &str) -> Result<(Vec<TextArgument>, ASTPoint), LoadError> {
+    let encoded = ron::from_str(source).map_err(|e| LoadError::Decode(e.to_string()))?;
+    load_encoded(encoded, "<ron>")
+}
+
+#[cfg(feature = "serde")]
+fn load_encoded(encoded: Encoded, name: &str) -> Result<(Vec<TextArgument>, ASTPoint), LoadError> {
+    let Encoded { mut texts, ast } = encoded;
+    check_encoded(&ast, &texts)?;
+    match ast {
+        ASTPoint::Element(_) | ASTPoint::Joint(..) | ASTPoint::Include(_) => {}
+        _ => {
+            return Err(LoadError::Decode(
+                "the root of a document has to be a single node".into(),
+            ))
+        }
+    }
+    let mut stack = Stack::new();
+    stack.push((PathBuf::from(name), name.to_string()));
+    let ast = resolve(&ast, Path::new(name), 0, &mut stack, &mut texts)?;
+    Ok((texts, ast))
+}
+
+/// Checks a tree which didn't come from the parser: every text it refers to has to exist,
+/// and components have to be expanded already, since their definitions aren't encoded.
+#[cfg(feature = "serde")]
+fn check_encoded(point: &ASTPoint, texts: &[TextArgument]) -> Result<(), LoadError> {
+    let node = |node: &Node| match node.ntype {
+        NType::Text(index) if index >= texts.len() => Err(LoadError::Decode(format!(
+            "text {} doesn't exist, there are only {} texts",
+            index,
+            texts.len()
+        ))),
+        _ => Ok(()),
+    };
+    let body = |body: &[ASTPoint]| body.iter().try_for_each(|x| check_encoded(x, texts));
+    match point {
+        ASTPoint::Element(x) => node(x),
+        ASTPoint::Joint(x, children) => node(x).and_then(|_| body(children)),
+        ASTPoint::If(_, children) | ASTPoint::For(_, children) => body(children),
+        ASTPoint::Use(x) => Err(LoadError::Decode(format!(
+            "the component `{}` has to be expanded before the tree is encoded",
+            x.name
+        ))),
+        ASTPoint::Include(_) => Ok(()),
+    }
+}
+
+/// Whether the document at `path` is an XHTML mockup rather than an `.azd` document.
+fn is_html(path: &Path) -> bool {
+    match path.extension().and_then(|x| x.to_str()) {
+        Some(x) => ["html", "htm", "xhtml"].contains(&&*x.to_lowercase()),
+        None => false,
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Parses the document at `path`, whose contents are `source`, and splices in its includes.
+fn load_source(
+    path: &Path,
+    source: &str,
+    stack: &mut Stack,
+    texts: &mut Vec<TextArgument>,
+) -> Result<ASTPoint, LoadError> {
+    let name = path.to_string_lossy();
+    let (own_texts, ast) = if is_html(path) {
+        html::parse(source, &name)?
+    } else {
+        parse::parse(&mut VecDeque::from(tokenize(source, &name)?))?
+    };
+    let offset = texts.len();
+    texts.extend(own_texts);
+    stack.push((canonical(path), name.into_owned()));
+    let ast = resolve(&ast, path, offset, stack, texts);
+    stack.pop();
+    ast
+}
+
+/// Replaces the includes in `point`, and moves its texts past those of earlier documents.
+fn resolve(
+    point: &ASTPoint,
+    path: &Path,
+    offset: usize,
+    stack: &mut Stack,
+    texts: &mut Vec<TextArgument>,
+) -> Result<ASTPoint, LoadError> {
+    let shift = |node: &Node| match node.ntype {
+        NType::Text(index) => Node {
+            ntype: NType::Text(index + offset),
+            ..node.clone()
+        },
+        _ => node.clone(),
+    };
+    let body = |body: &[ASTPoint], stack: &mut Stack, texts: &mut Vec<TextArgument>| {
+        body.iter()
+            .map(|x| resolve(x, path, offset, stack, texts))
+            .collect::<Result<Vec<ASTPoint>, LoadError>>()
+    };
+    Ok(match point {
+        ASTPoint::Element(x) => ASTPoint::Element(shift(x)),
+        ASTPoint::Joint(x, children) => ASTPoint::Joint(shift(x), body(children, stack, texts)?),
+        ASTPoint::If(x, children) => ASTPoint::If(x.clone(), body(children, stack, texts)?),
+        ASTPoint::For(x, children) => ASTPoint::For(x.clone(), body(children, stack, texts)?),
+        ASTPoint::Use(_) => point.clone(),
+        ASTPoint::Include(x) => return include(x, path, stack, texts),
+    })
+}
+
+/// Loads the document `include` refers to, relative to the document at `path`.
+fn include(
+    include: &Include,
+    path: &Path,
+    stack: &mut Stack,
+    texts: &mut Vec<TextArgument>,
+) -> Result<ASTPoint, LoadError> {
+    let target = path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(&include.path);
+    let id = canonical(&target);
+    if let Some(start) = stack.iter().position(|(x, _)| *x == id) {
+        let mut cycle: Vec<&str> = stack[start..].iter().map(|(_, x)| &**x).collect();
+        cycle.push(&include.path);
+        return Err(LoadError::Include(
+            Diagnostic::new(
+                format!("{:?} includes itself", include.path),
+                include.span.clone(),
+            )
+            .with_help(format!(
+                "includes can't be recursive: {}",
+                cycle.join(" -> ")
+            )),
+        ));
+    }
+    let source = fs::read_to_string(&target).map_err(|e| {
+        LoadError::Include(
+            Diagnostic::new(
+                format!("Could not read {}: {}", target.display(), e),
+                include.span.clone(),
+            )
+            .with_help("includes are found relative to the document which includes them"),
+        )
+    })?;
+    match load_source(&target, &source, stack, texts) {
+        Ok(x) => Ok(x),
+        Err(error) => Err(LoadError::Included {
+            document: source,
+            from: include.span.clone(),
+            error: Box::new(error),
+        }),
+    }
+}
@@ -0,0 +1,1280 @@
+use crate::bindings::{check_template, has_bindings, interpolate, TEMPLATE_HELP};
+use crate::diagnostics::Diagnostic;
+use crate::error::LoadError;
+use crate::expand;
+use crate::print::escape;
+use crate::span::{Span, Spanned};
+use crate::tokenize::{Number, Token};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NType {
+    Div,
+    Label(String),
+    Image(String),
+    /// A text whose contents are at this index in the list of `TextArgument`s,
+    /// to be cached up front.
+    Text(usize),
+    /// A text which refers to bindings, so it can't be cached and is laid out like a label.
+    BoundText(TextArgument),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextArgument {
+    pub body: String,
+    pub font: Option<String>,
+    pub size: Option<Number>,
+}
+
+impl TextArgument {
+    /// The font and size as CSS declarations, for texts with bindings: they can't be
+    /// cached with them, so they are laid out like labels which look the same.
+    pub fn declarations(&self) -> Vec<Declaration> {
+        let font = self.font.as_ref().map(|x| ("font-family", escape(x)));
+        // Sizes without a unit are in pixels, like the ones of cached texts.
+        let size = self.size.map(|x| match x.unit {
+            None => ("font-size", format!("{}px", x.value)),
+            Some(_) => ("font-size", x.to_string()),
+        });
+        font.into_iter()
+            .chain(size)
+            .map(|(property, value)| Declaration {
+                property: property.to_string(),
+                value,
+            })
+            .collect()
+    }
+}
+
+/// The value of an attribute: `key = value`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Value {
+    Str(String),
+    Num(Number),
+    Bool(bool),
+    Ident(String),
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Node {
+    pub ntype: NType,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub id: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub classes: Vec<String>,
+    /// The `key = value` pairs from the node's argument list, spanning both key and value.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: BTreeMap<String, Spanned<Value>>,
+    /// Inline style, from `style = "..."` and then a `{ ... }` block, in order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub style: Vec<Spanned<Declaration>>,
+    /// From the node's type up to the end of its arguments.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub span: Span,
+}
+
+/// `property: value`, a CSS declaration in a node's inline style.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Declaration {
+    pub property: String,
+    pub value: String,
+}
+
+/// `if(name)` or `if(!name)`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Condition {
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub negated: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub span: Span,
+}
+
+/// `for(item in list)`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Loop {
+    pub item: String,
+    pub list: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub span: Span,
+}
+
+/// `name(arguments);`, where a component is used.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Use {
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub arguments: Vec<Spanned<Value>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub span: Span,
+}
+
+/// `include("path");`, where another document's tree is spliced in by the loader.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Include {
+    pub path: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub span: Span,
+}
+
+/// `component name(parameters)[ body ]`, a subtree which can be used by name.
+/// `{parameter}` in the body's strings is replaced by the argument it is used with.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Component {
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub parameters: Vec<String>,
+    pub body: Vec<ASTPoint>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ASTPoint {
+    Element(Node),
+    Joint(Node, Vec<ASTPoint>),
+    /// Children which are only there when the condition holds.
+    If(Condition, Vec<ASTPoint>),
+    /// Children which are repeated for every record in a list.
+    For(Loop, Vec<ASTPoint>),
+    /// A component, until `expand::expand` replaces it by the component's body.
+    Use(Use),
+    /// Another document, until `load::load_document` replaces it by that document's root.
+    Include(Include),
+}
+
+impl ASTPoint {
+    pub fn span(&self) -> &Span {
+        match self {
+            ASTPoint::Element(x) => &x.span,
+            ASTPoint::Joint(x, _) => &x.span,
+            ASTPoint::If(x, _) => &x.span,
+            ASTPoint::For(x, _) => &x.span,
+            ASTPoint::Use(x) => &x.span,
+            ASTPoint::Include(x) => &x.span,
+        }
+    }
+}
+
+/// Everything the parser could make of a document, before components are expanded.
+///
+/// Nodes which failed to parse are left out of `ast`, so it is only complete
+/// when `diagnostics` is empty. `ast` is `None` when not even the root node could be read.
+#[derive(Debug)]
+pub struct ParseOutput {
+    pub texts: Vec<TextArgument>,
+    pub components: Vec<Component>,
+    pub ast: Option<ASTPoint>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// The tokens left to parse, along with the last one taken so that running out of
+/// tokens can be reported somewhere sensible.
+struct Tokens {
+    queue: VecDeque<Spanned<Token>>,
+    last: Span,
+    last_token: Option<Token>,
+}
+
+impl Tokens {
+    /// Takes the next token of the current node.
+    /// A `]` is never taken since it belongs to the parent, see `close`.
+    fn next(&mut self) -> Result<Spanned<Token>, Diagnostic> {
+        if let Some(x) = self.queue.front() {
+            if x.node == Token::CloseBracket {
+                return Err(Diagnostic::new(
+                    "Unexpected `]` in the middle of a node",
+                    x.span.clone(),
+                )
+                .with_help("every node ends with either `;` or a `[ ... ]` block of children"));
+            }
+        }
+        self.take()
+    }
+
+    /// Takes the `]` which ends a block of children.
+    fn close(&mut self) {
+        let _ = self.take();
+    }
+
+    fn take(&mut self) -> Result<Spanned<Token>, Diagnostic> {
+        match self.queue.pop_front() {
+            Some(x) => {
+                self.last = x.span.clone();
+                self.last_token = Some(x.node.clone());
+                Ok(x)
+            }
+            None => Err(end_of_document(&self.last)),
+        }
+    }
+
+    fn peek(&self) -> Result<&Spanned<Token>, Diagnostic> {
+        self.queue
+            .front()
+            .ok_or_else(|| end_of_document(&self.last))
+    }
+}
+
+fn end_of_document(last: &Span) -> Diagnostic {
+    Diagnostic::new("Unexpected end of input", last.clone())
+        .with_help("every node ends with either `;` or a `[ ... ]` block of children")
+}
+
+/// How deeply brackets can be nested, so that no document overflows the stack of
+/// anything walking its tree.
+pub const MAX_DEPTH: usize = 128;
+
+/// Finds every unmatched bracket or parenthesis, and brackets nested too deeply.
+fn check_brackets(tokens: &VecDeque<Spanned<Token>>) -> Vec<Diagnostic> {
+    #[derive(PartialEq, Debug)]
+    enum Bracket {
+        Paren,
+        Square,
+    }
+
+    let mut depths: Vec<(Bracket, &Span)> = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    for i in tokens {
+        match i.node {
+            Token::OpenParen => {
+                depths.push((Bracket::Paren, &i.span));
+            }
+            Token::OpenBracket => {
+                depths.push((Bracket::Square, &i.span));
+                if depths.len() > MAX_DEPTH {
+                    diagnostics.push(
+                        Diagnostic::new(
+                            format!("Nodes are nested more than {} levels deep", MAX_DEPTH),
+                            i.span.clone(),
+                        )
+                        .with_help("split the document up with components or includes"),
+                    );
+                    return diagnostics;
+                }
+            }
+            Token::CloseParen => match depths.last() {
+                Some((Bracket::Paren, _)) => {
+                    depths.pop();
+                }
+                Some((Bracket::Square, _)) => diagnostics.push(Diagnostic::new(
+                    "Attempted to close a square bracket with a parenthesis",
+                    i.span.clone(),
+                )),
+                None => diagnostics.push(Diagnostic::new(
+                    "Attempted to close a parenthesis which didn't exist",
+                    i.span.clone(),
+                )),
+            },
+            Token::CloseBracket => match depths.last() {
+                Some((Bracket::Square, _)) => {
+                    depths.pop();
+                }
+                Some((Bracket::Paren, _)) => diagnostics.push(Diagnostic::new(
+                    "Attempted to close a parenthesis with a square bracket",
+                    i.span.clone(),
+                )),
+                None => diagnostics.push(Diagnostic::new(
+                    "Attempted to close a square bracket which didn't exist",
+                    i.span.clone(),
+                )),
+            },
+            _ => {}
+        }
+    }
+
+    for (bracket, span) in depths {
+        let message = match bracket {
+            Bracket::Paren => "Unclosed parenthesis",
+            Bracket::Square => "Unclosed square bracket",
+        };
+        diagnostics.push(Diagnostic::new(message, span.clone()));
+    }
+
+    diagnostics
+}
+
+/// Skips the rest of a node which failed to parse so that its siblings can still be read.
+/// Stops after the node's `;`, after its block of children, or before its parent's `]`.
+/// The skipped children are still parsed so that their problems are reported too.
+fn recover(source: &mut Tokens, diagnostics: &mut Vec<Diagnostic>) {
+    // The token the error was found at might already have ended the node.
+    match source.last_token {
+        Some(Token::Semicolon) => return,
+        Some(Token::OpenBracket) => {
+            parse_in(source, &mut Vec::new(), diagnostics);
+            return;
+        }
+        _ => {}
+    }
+    // The error might have happened inside of the node's parentheses,
+    // so closing more than were opened here is fine.
+    let mut parens = 0;
+    while let Some(x) = source.queue.front() {
+        match x.node {
+            Token::OpenParen => parens += 1,
+            Token::CloseParen if parens > 0 => parens -= 1,
+            Token::Semicolon if parens == 0 => {
+                let _ = source.take();
+                return;
+            }
+            Token::OpenBracket if parens == 0 => {
+                let _ = source.take();
+                parse_in(source, &mut Vec::new(), diagnostics);
+                return;
+            }
+            Token::CloseBracket => return,
+            _ => {}
+        }
+        let _ = source.take();
+    }
+}
+
+/// Reads a binding's name, `record.field` for the fields of a loop's records.
+fn parse_path(source: &mut Tokens) -> Result<String, Diagnostic> {
+    let first = source.next()?;
+    let mut path = match first.node {
+        Token::Ident(x) => x,
+        x => {
+            return Err(
+                Diagnostic::new(format!("Expected a name, found {:?}", x), first.span)
+                    .with_help("conditions and lists are the names of bindings"),
+            )
+        }
+    };
+    // `.field` is read as a class.
+    while let Some(Token::Class(_)) = source.queue.front().map(|x| &x.node) {
+        if let Token::Class(x) = source.next()?.node {
+            path.push('.');
+            path += &x;
+        }
+    }
+    Ok(path)
+}
+
+fn expect(source: &mut Tokens, token: Token, help: &str) -> Result<Span, Diagnostic> {
+    let next = source.next()?;
+    if next.node != token {
+        return Err(Diagnostic::new(
+            format!("Expected {:?}, found {:?}", token, next.node),
+            next.span,
+        )
+        .with_help(help));
+    }
+    Ok(next.span)
+}
+
+/// Reads the `[ ... ]` after an `if` or a `for`.
+fn parse_block(
+    source: &mut Tokens,
+    text_strings: &mut Vec<TextArgument>,
+    diagnostics: &mut Vec<Diagnostic>,
+    help: &str,
+) -> Result<Vec<ASTPoint>, Diagnostic> {
+    let open = expect(source, Token::OpenBracket, help)?;
+    let empty = closes_at_once(source);
+    let body = parse_in(source, text_strings, diagnostics);
+    if empty {
+        diagnostics.push(Diagnostic::new(
+            "Empty list of children",
+            open.to(&source.last),
+        ));
+    }
+    Ok(body)
+}
+
+/// Whether the `[` which was just read is closed right away, as opposed to
+/// having children which are all broken.
+fn closes_at_once(source: &Tokens) -> bool {
+    source.peek().is_ok_and(|x| x.node == Token::CloseBracket)
+}
+
+const IF_HELP: &str = "conditions are written as `if(name)[ ... ]` or `if(!name)[ ... ]`";
+const FOR_HELP: &str = "loops are written as `for(item in list)[ ... ]`";
+
+fn parse_if(
+    source: &mut Tokens,
+    text_strings: &mut Vec<TextArgument>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<ASTPoint, Diagnostic> {
+    let start = source.next()?.span;
+    expect(source, Token::OpenParen, IF_HELP)?;
+    let negated = source.peek()?.node == Token::Not;
+    if negated {
+        source.next()?;
+    }
+    let name = parse_path(source)?;
+    expect(source, Token::CloseParen, IF_HELP)?;
+    let condition = Condition {
+        name,
+        negated,
+        span: start.to(&source.last),
+    };
+    let body = parse_block(source, text_strings, diagnostics, IF_HELP)?;
+    Ok(ASTPoint::If(condition, body))
+}
+
+fn parse_for(
+    source: &mut Tokens,
+    text_strings: &mut Vec<TextArgument>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<ASTPoint, Diagnostic> {
+    let start = source.next()?.span;
+    expect(source, Token::OpenParen, FOR_HELP)?;
+    let item = source.next()?;
+    let item = match item.node {
+        Token::Ident(x) => x,
+        x => {
+            return Err(Diagnostic::new(
+                format!("Expected a name for the records, found {:?}", x),
+                item.span,
+            )
+            .with_help(FOR_HELP))
+        }
+    };
+    expect(source, Token::In, FOR_HELP)?;
+    let list = parse_path(source)?;
+    expect(source, Token::CloseParen, FOR_HELP)?;
+    let header = Loop {
+        item,
+        list,
+        span: start.to(&source.last),
+    };
+    let body = parse_block(source, text_strings, diagnostics, FOR_HELP)?;
+    Ok(ASTPoint::For(header, body))
+}
+
+const COMPONENT_HELP: &str = "components are defined as `component card(title, img)[ ... ]` \
+                              and used as `card(\"Hi\", \"catimg\");`";
+
+fn parse_use(source: &mut Tokens) -> Result<ASTPoint, Diagnostic> {
+    let head = source.next()?;
+    let name = match head.node {
+        Token::Ident(x) => x,
+        x => {
+            return Err(Diagnostic::new(
+                format!("Expected a component, found {:?}", x),
+                head.span,
+            ))
+        }
+    };
+    let arguments = if source.peek()?.node == Token::OpenParen {
+        let x = parse_arguments(source, "Components", COMPONENT_HELP)?;
+        if let Some(x) = x.attributes.values().next() {
+            return Err(Diagnostic::new(
+                "Components take their arguments in the order of their parameters",
+                x.span.clone(),
+            )
+            .with_help(COMPONENT_HELP));
+        }
+        // Arguments are filled into labels and texts as they were written.
+        for x in &x.positional {
+            if let Value::Str(ref text) = x.node {
+                template(text, &x.span)?;
+            }
+        }
+        x.positional
+    } else {
+        Vec::new()
+    };
+    let span = head.span.to(&source.last);
+    let end = source.next()?;
+    if end.node != Token::Semicolon {
+        return Err(Diagnostic::new(
+            format!("Expected `;` after a component, found {:?}", end.node),
+            end.span,
+        )
+        .with_help("components can't be given children"));
+    }
+    Ok(ASTPoint::Use(Use {
+        name,
+        arguments,
+        span,
+    }))
+}
+
+fn parse_include(source: &mut Tokens) -> Result<ASTPoint, Diagnostic> {
+    let start = source.next()?.span;
+    let help = "includes take the path of a document: include(\"header.azd\");";
+    let arguments = parse_arguments(source, "Includes", help)?;
+    let path = single_string(&arguments, "Includes", help)?;
+    let span = start.to(&source.last);
+    let end = source.next()?;
+    if end.node != Token::Semicolon {
+        return Err(Diagnostic::new(
+            format!("Expected `;` after an include, found {:?}", end.node),
+            end.span,
+        )
+        .with_help(help));
+    }
+    Ok(ASTPoint::Include(Include { path, span }))
+}
+
+fn parse_component(
+    source: &mut Tokens,
+    text_strings: &mut Vec<TextArgument>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Component, Diagnostic> {
+    let start = source.next()?.span;
+    let name = source.next()?;
+    let name = match name.node {
+        Token::Ident(x) => x,
+        x => {
+            return Err(Diagnostic::new(
+                format!("Expected the name of the component, found {:?}", x),
+                name.span,
+            )
+            .with_help(COMPONENT_HELP))
+        }
+    };
+    let mut parameters = Vec::new();
+    if source.peek()?.node == Token::OpenParen {
+        let arguments = parse_arguments(source, "Components", COMPONENT_HELP)?;
+        if let Some((key, x)) = arguments.attributes.iter().next() {
+            return Err(Diagnostic::new(
+                format!("Parameters are only names, found `{} = ...`", key),
+                x.span.clone(),
+            )
+            .with_help(COMPONENT_HELP));
+        }
+        for x in arguments.positional {
+            match x.node {
+                Value::Ident(name) => parameters.push(name),
+                _ => {
+                    return Err(Diagnostic::new("Expected the name of a parameter", x.span)
+                        .with_help(COMPONENT_HELP))
+                }
+            }
+        }
+    }
+    let span = start.to(&source.last);
+    let body = parse_block(source, text_strings, diagnostics, COMPONENT_HELP)?;
+    Ok(Component {
+        name,
+        parameters,
+        body,
+        span,
+    })
+}
+
+fn parse_next(
+    source: &mut Tokens,
+    text_strings: &mut Vec<TextArgument>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<ASTPoint, Diagnostic> {
+    match source.peek()?.node {
+        Token::If => return parse_if(source, text_strings, diagnostics),
+        Token::For => return parse_for(source, text_strings, diagnostics),
+        Token::Ident(_) => return parse_use(source),
+        Token::Include => return parse_include(source),
+        Token::Component => {
+            return Err(Diagnostic::new(
+                "Components can only be defined at the top of a document",
+                source.next()?.span,
+            ))
+        }
+        _ => {}
+    }
+    let texts_before = text_strings.len();
+    let head = parse_next_node(source, text_strings)?;
+    let next = source.peek()?.clone();
+    match next.node {
+        Token::OpenBracket => {
+            let _ = source.next();
+            let empty = closes_at_once(source);
+            let body = parse_in(source, text_strings, diagnostics);
+            if empty {
+                diagnostics.push(
+                    Diagnostic::new("Empty list of children", next.span.to(&source.last))
+                        .with_help("nodes without children end with `;` instead of `[]`"),
+                );
+            }
+            if body.is_empty() {
+                return Ok(ASTPoint::Element(head));
+            }
+            Ok(ASTPoint::Joint(head, body))
+        }
+        Token::Semicolon => {
+            let _ = source.next();
+            Ok(ASTPoint::Element(head))
+        }
+        x => {
+            // The node is dropped, so its text mustn't be created either.
+            text_strings.truncate(texts_before);
+            Err(Diagnostic::new(
+                format!("Expected `;` or `[` after a node, found {:?}", x),
+                next.span,
+            )
+            .with_help("end a node with `;`, or give it children with `[ ... ]`"))
+        }
+    }
+}
+
+/// Reads the `#id` and `.class`es which follow a node's type, in any order.
+fn parse_selectors(source: &mut Tokens) -> Result<(Option<String>, Vec<String>), Diagnostic> {
+    let mut id: Option<String> = None;
+    let mut classes = Vec::new();
+    while let Some(Token::Id(_)) | Some(Token::Class(_)) = source.queue.front().map(|x| &x.node) {
+        let token = source.next()?;
+        match token.node {
+            Token::Class(x) => classes.push(x),
+            Token::Id(_) if id.is_some() => {
+                return Err(
+                    Diagnostic::new("A node can only have one `#id`", token.span)
+                        .with_help("more ids can be given with the `id` attribute: id=\"a b\""),
+                );
+            }
+            Token::Id(x) => id = Some(x),
+            _ => {}
+        }
+    }
+    Ok((id, classes))
+}
+
+const STYLE_HELP: &str = "inline styles are CSS declarations: \
+                          div(style = \"width: 100px; color: red\") or div{ width: 100px; }";
+
+/// Takes the style block after a node's type and selectors, if there is one.
+fn style_block(source: &mut Tokens) -> Option<Spanned<String>> {
+    match source.queue.front() {
+        Some(Spanned {
+            node: Token::Style(_),
+            ..
+        }) => match source.take() {
+            Ok(Spanned {
+                node: Token::Style(x),
+                span,
+            }) => Some(Spanned::new(x, span)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The span of `contents[start..end]`, where `contents` are those of the block at `span`.
+fn span_within(span: &Span, contents: &str, start: usize, end: usize) -> Span {
+    let before = &contents[..start];
+    let column = match before.rfind('\n') {
+        Some(x) => before[x + 1..].chars().count() + 1,
+        // Past the `{`.
+        None => span.column + 1 + before.chars().count(),
+    };
+    Span {
+        file: span.file.clone(),
+        line: span.line + before.matches('\n').count(),
+        column,
+        start: span.start + 1 + start,
+        end: span.start + 1 + end,
+    }
+}
+
+/// Splits inline style at the `;`s which end declarations, as opposed to the ones
+/// inside of strings or parentheses: `content: ";"`, `background: url(a;b)`.
+fn split_declarations(contents: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in contents.char_indices() {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(x), y) if x == y => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth = depth.saturating_sub(1),
+            (None, ';') if depth == 0 => {
+                parts.push(&contents[start..index]);
+                start = index + 1;
+            }
+            (None, _) => {}
+        }
+    }
+    parts.push(&contents[start..]);
+    parts
+}
+
+/// Splits inline style into its declarations. Those of a block each get a span of their
+/// own, while those of a string, whose escapes would throw the offsets off, share its span.
+fn parse_style(
+    contents: &str,
+    span: &Span,
+    block: bool,
+) -> Result<Vec<Spanned<Declaration>>, Diagnostic> {
+    let mut declarations = Vec::new();
+    let mut offset = 0;
+    for part in split_declarations(contents) {
+        let start = offset + part.len() - part.trim_start().len();
+        let end = offset + part.trim_end().len();
+        offset += part.len() + 1;
+        if start >= end {
+            continue;
+        }
+        let span = if block {
+            span_within(span, contents, start, end)
+        } else {
+            span.clone()
+        };
+        let declaration = &contents[start..end];
+        let is_property =
+            |x: &str| !x.is_empty() && x.chars().all(|x| x.is_ascii_alphanumeric() || x == '-');
+        match declaration.split_once(':') {
+            Some((property, value)) if is_property(property.trim()) && !value.trim().is_empty() => {
+                let declaration = Declaration {
+                    property: property.trim().to_string(),
+                    value: value.trim().to_string(),
+                };
+                declarations.push(Spanned::new(declaration, span));
+            }
+            _ => {
+                return Err(Diagnostic::new(
+                    format!("Expected `property: value`, found `{}`", declaration),
+                    span,
+                )
+                .with_help(STYLE_HELP))
+            }
+        }
+    }
+    Ok(declarations)
+}
+
+/// What was inside of a node's `( ... )`.
+struct Arguments {
+    positional: Vec<Spanned<Value>>,
+    attributes: BTreeMap<String, Spanned<Value>>,
+    span: Span,
+}
+
+/// The attributes which attach a callback, such as `on_click=increment`.
+pub const EVENT_ATTRIBUTES: &[&str] = &[
+    "on_click",
+    "on_mouse_up",
+    "on_mouse_down",
+    "on_mouse_over",
+    "on_mouse_enter",
+    "on_mouse_leave",
+    "on_scroll",
+    "on_text_input",
+    "on_key_down",
+    "on_key_up",
+];
+
+fn invalid_attribute(key: &str, value: &Spanned<Value>, help: &str) -> Diagnostic {
+    Diagnostic::new(
+        format!("Invalid value for `{}`: {:?}", key, value.node),
+        value.span.clone(),
+    )
+    .with_help(help)
+}
+
+/// Checks that `key` is an attribute nodes can have, and that `value` is one it takes.
+pub fn check_attribute(key: &str, value: &Spanned<Value>) -> Result<(), Diagnostic> {
+    match (key, &value.node) {
+        (x, Value::Ident(_)) if EVENT_ATTRIBUTES.contains(&x) => Ok(()),
+        (x, _) if EVENT_ATTRIBUTES.contains(&x) => Err(invalid_attribute(
+            key,
+            value,
+            "callbacks are referred to by their name: on_click=increment",
+        )),
+        ("id", Value::Str(_)) | ("class", Value::Str(_)) => Ok(()),
+        ("tabindex", Value::Num(x)) if x.index().is_some() => Ok(()),
+        ("tabindex", Value::Ident(x)) if x == "auto" => Ok(()),
+        ("tabindex", Value::Bool(false)) | ("draggable", Value::Bool(_)) => Ok(()),
+        ("id", _) | ("class", _) => Err(invalid_attribute(
+            key,
+            value,
+            "this takes a string of space separated names",
+        )),
+        ("tabindex", _) => Err(invalid_attribute(
+            key,
+            value,
+            "tabindex takes a whole number below 16777216, `auto` or `false`",
+        )),
+        ("draggable", _) => Err(invalid_attribute(
+            key,
+            value,
+            "draggable takes `true` or `false`",
+        )),
+        _ => Err(
+            Diagnostic::new(format!("Unknown attribute `{}`", key), value.span.clone()).with_help(
+                "the attributes are `id`, `class`, `tabindex`, `draggable`, `style` \
+                 and `on_*` callbacks like `on_click`",
+            ),
+        ),
+    }
+}
+
+fn parse_value(token: Spanned<Token>) -> Result<Spanned<Value>, Diagnostic> {
+    let value = match token.node {
+        Token::Str(x) => Value::Str(x),
+        Token::Num(x) => Value::Num(x),
+        Token::Ident(ref x) if x == "true" => Value::Bool(true),
+        Token::Ident(ref x) if x == "false" => Value::Bool(false),
+        Token::Ident(x) => Value::Ident(x),
+        x => {
+            return Err(
+                Diagnostic::new(format!("Expected a value, found {:?}", x), token.span)
+                    .with_help("values are strings, numbers, `true`, `false` or names"),
+            )
+        }
+    };
+    Ok(Spanned::new(value, token.span))
+}
+
+/// Reads `( ... )`: values and `key = value` attributes, optionally separated by commas.
+fn parse_arguments(source: &mut Tokens, what: &str, help: &str) -> Result<Arguments, Diagnostic> {
+    let open = source.next()?;
+    if open.node != Token::OpenParen {
+        return Err(
+            Diagnostic::new(format!("{} need an argument list", what), open.span).with_help(help),
+        );
+    }
+    let mut arguments = Arguments {
+        positional: Vec::new(),
+        attributes: BTreeMap::new(),
+        span: open.span,
+    };
+    loop {
+        match source.peek()?.node {
+            Token::CloseParen => break,
+            Token::Comma => {
+                source.next()?;
+                continue;
+            }
+            _ => {}
+        }
+        let is_attribute = match source.queue.get(1) {
+            Some(x) => x.node == Token::Equals,
+            None => false,
+        };
+        let token = source.next()?;
+        if !is_attribute {
+            arguments.positional.push(parse_value(token)?);
+            continue;
+        }
+        let key = match token.node {
+            Token::Ident(x) => x,
+            x => {
+                return Err(Diagnostic::new(
+                    format!("Expected the name of an attribute, found {:?}", x),
+                    token.span,
+                )
+                .with_help("attributes are written as `name = value`"))
+            }
+        };
+        source.next()?;
+        let value = parse_value(source.next()?)?;
+        // Point at the whole `key = value` so that problems with it are easy to find.
+        let value = Spanned::new(value.node, token.span.to(&value.span));
+        if arguments.attributes.contains_key(&key) {
+            return Err(Diagnostic::new(
+                format!("The attribute `{}` was given more than once", key),
+                value.span,
+            ));
+        }
+        arguments.attributes.insert(key, value);
+    }
+    source.next()?;
+    arguments.span = arguments.span.to(&source.last);
+    Ok(arguments)
+}
+
+/// Checks that the arguments are exactly one string, as taken by labels and images.
+fn single_string(arguments: &Arguments, what: &str, help: &str) -> Result<String, Diagnostic> {
+    match arguments.positional.first() {
+        Some(Spanned {
+            node: Value::Str(x),
+            ..
+        }) => {
+            if let Some(x) = arguments.positional.get(1) {
+                return Err(Diagnostic::new(
+                    format!("{} take exactly one string", what),
+                    x.span.clone(),
+                )
+                .with_help(help));
+            }
+            Ok(x.clone())
+        }
+        Some(x) => {
+            Err(Diagnostic::new(format!("{} take a string", what), x.span.clone()).with_help(help))
+        }
+        None => Err(
+            Diagnostic::new(format!("{} take a string", what), arguments.span.clone())
+                .with_help(help),
+        ),
+    }
+}
+
+/// Makes sure the `{name}`s in a label or text can be filled in, so that a
+/// malformed one is found when loading rather than on every layout.
+fn template(text: &str, span: &Span) -> Result<(), Diagnostic> {
+    check_template(text).map_err(|x| Diagnostic::new(x, span.clone()).with_help(TEMPLATE_HELP))
+}
+
+const TEXT_HELP: &str = "text takes its body, then optionally a font and a size, or any \
+                         of them by name: text(\"Lorem ipsum\", size = 12pt, align = center)";
+
+/// Takes the argument called `name` out of the attributes, or the positional one at `index`.
+fn text_field(
+    arguments: &mut Arguments,
+    name: &str,
+    index: usize,
+) -> Result<Option<Spanned<Value>>, Diagnostic> {
+    let named = arguments.attributes.remove(name);
+    match (arguments.positional.get(index), named) {
+        (Some(_), Some(x)) => Err(Diagnostic::new(
+            format!("The {} of the text was given twice", name.replace('_', " ")),
+            x.span,
+        )
+        .with_help(TEXT_HELP)),
+        (Some(x), None) => Ok(Some(x.clone())),
+        (None, x) => Ok(x),
+    }
+}
+
+/// Checks the size of a text, or its line height.
+fn text_number(value: Option<Spanned<Value>>, what: &str) -> Result<Option<Number>, Diagnostic> {
+    match value {
+        Some(Spanned {
+            node: Value::Num(x),
+            span,
+        }) => {
+            if x.value < 0.0 {
+                return Err(Diagnostic::new(
+                    format!("The {} of a text can't be negative", what),
+                    span,
+                )
+                .with_help(TEXT_HELP));
+            }
+            Ok(Some(x))
+        }
+        Some(x) => Err(Diagnostic::new(
+            format!("Expected the {} of the text, found {:?}", what, x.node),
+            x.span,
+        )
+        .with_help(TEXT_HELP)),
+        None => Ok(None),
+    }
+}
+
+/// Checks the arguments of a text, which are `body`, `font` and `size` in that order,
+/// or any of them by name. The named ones are taken out of the node's attributes.
+fn text_argument(arguments: &mut Arguments) -> Result<TextArgument, Diagnostic> {
+    if let Some(x) = arguments.positional.get(3) {
+        return Err(Diagnostic::new(
+            "Unexpected argument after the size of a text",
+            x.span.clone(),
+        )
+        .with_help(TEXT_HELP));
+    }
+    let body = match text_field(arguments, "body", 0)? {
+        Some(Spanned {
+            node: Value::Str(x),
+            span,
+        }) => {
+            template(&x, &span)?;
+            x
+        }
+        x => {
+            let span = x.map_or_else(|| arguments.span.clone(), |x| x.span);
+            return Err(
+                Diagnostic::new("Texts should always have a string as their body", span)
+                    .with_help(TEXT_HELP),
+            );
+        }
+    };
+    let font = match text_field(arguments, "font", 1)? {
+        Some(Spanned {
+            node: Value::Str(x),
+            ..
+        }) => Some(x),
+        Some(x) => {
+            return Err(Diagnostic::new(
+                format!("Expected the font of the text, found {:?}", x.node),
+                x.span,
+            )
+            .with_help(TEXT_HELP))
+        }
+        None => None,
+    };
+    let size = text_number(text_field(arguments, "size", 2)?, "size")?;
+    Ok(TextArgument { body, font, size })
+}
+
+/// Takes `line_height` and `align` out of a text's attributes as the inline style they
+/// stand for, since they only matter for how the text is laid out, not for caching it.
+fn text_layout(arguments: &mut Arguments) -> Result<Vec<Spanned<Declaration>>, Diagnostic> {
+    let mut style = Vec::new();
+    if let Some(x) = arguments.attributes.remove("line_height") {
+        if let Some(height) = text_number(Some(x.clone()), "line height")? {
+            let declaration = Declaration {
+                property: "line-height".to_string(),
+                value: height.to_string(),
+            };
+            style.push(Spanned::new(declaration, x.span));
+        }
+    }
+    match arguments.attributes.remove("align") {
+        Some(Spanned {
+            node: Value::Ident(x),
+            span,
+        }) if x == "left" || x == "center" || x == "right" => {
+            let declaration = Declaration {
+                property: "text-align".to_string(),
+                value: x,
+            };
+            style.push(Spanned::new(declaration, span));
+        }
+        Some(x) => {
+            return Err(Diagnostic::new(
+                format!("Expected `left`, `center` or `right`, found {:?}", x.node),
+                x.span,
+            )
+            .with_help("texts are aligned with align = left, align = center or align = right"))
+        }
+        None => {}
+    }
+    Ok(style)
+}
+
+fn parse_next_node(
+    source: &mut Tokens,
+    text_strings: &mut Vec<TextArgument>,
+) -> Result<Node, Diagnostic> {
+    let head = source.next()?;
+    let start = head.span;
+    let ntype;
+    let arguments;
+    let mut style = Vec::new();
+    // Only created once the whole node has been read, so that broken nodes leave no texts behind.
+    let mut text_string = None;
+    let (id, classes) = match head.node {
+        Token::Div | Token::Label | Token::Image | Token::Text => parse_selectors(source)?,
+        _ => (None, Vec::new()),
+    };
+    // The style block comes right after the type and selectors, since a `{` anywhere
+    // else is a comment.
+    let block = style_block(source);
+    match head.node {
+        Token::Div => {
+            ntype = NType::Div;
+            arguments = if source.peek()?.node == Token::OpenParen {
+                let x =
+                    parse_arguments(source, "Divs", "attributes are written as `name = value`")?;
+                if let Some(x) = x.positional.first() {
+                    return Err(Diagnostic::new("Divs only take attributes", x.span.clone())
+                        .with_help("attributes are written as `name = value`"));
+                }
+                Some(x)
+            } else {
+                None
+            };
+        }
+        Token::Label => {
+            let help = "labels take exactly one string: label(\"abcd\")";
+            let x = parse_arguments(source, "Labels", help)?;
+            let text = single_string(&x, "Labels", help)?;
+            template(&text, &x.positional[0].span)?;
+            ntype = NType::Label(text);
+            arguments = Some(x);
+        }
+        Token::Image => {
+            let help = "images take the id of a loaded image: image(\"catimg\")";
+            let x = parse_arguments(source, "Images", help)?;
+            ntype = NType::Image(single_string(&x, "Images", help)?);
+            arguments = Some(x);
+        }
+        Token::Text => {
+            let mut x = parse_arguments(source, "Text nodes", TEXT_HELP)?;
+            let mut text = text_argument(&mut x)?;
+            style = text_layout(&mut x)?;
+            ntype = if has_bindings(&text.body) {
+                NType::BoundText(text)
+            } else {
+                // Nothing to fill in, but `{{` and `}}` still need unescaping.
+                if let Ok(x) = interpolate(&text.body, &()) {
+                    text.body = x;
+                }
+                text_string = Some(text);
+                NType::Text(text_strings.len())
+            };
+            arguments = Some(x);
+        }
+        x => {
+            return Err(
+                Diagnostic::new(format!("Expected a node, found {:?}", x), start)
+                    .with_help("nodes are one of `div`, `label`, `image` or `text`"),
+            )
+        }
+    }
+
+    let mut attributes = arguments.map(|x| x.attributes).unwrap_or_default();
+    // The more explicit a declaration, the later it comes, so that it is the one applied.
+    match attributes.remove("style") {
+        Some(Spanned {
+            node: Value::Str(x),
+            span,
+        }) => style.extend(parse_style(&x, &span, false)?),
+        Some(x) => {
+            return Err(Diagnostic::new(
+                format!(
+                    "Expected a string of style declarations, found {:?}",
+                    x.node
+                ),
+                x.span,
+            )
+            .with_help(STYLE_HELP))
+        }
+        None => {}
+    }
+    for (key, value) in &attributes {
+        check_attribute(key, value)?;
+    }
+    if let Some(x) = block {
+        style.extend(parse_style(&x.node, &x.span, true)?);
+    }
+    text_strings.extend(text_string);
+
+    Ok(Node {
+        ntype,
+        id,
+        classes,
+        attributes,
+        style,
+        span: start.to(&source.last),
+    })
+}
+
+/// Reads children up to and including the closing `]`, skipping over the ones which are broken.
+fn parse_in(
+    source: &mut Tokens,
+    text_strings: &mut Vec<TextArgument>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<ASTPoint> {
+    let mut points: Vec<ASTPoint> = Vec::new();
+    loop {
+        match source.peek() {
+            Ok(x) if x.node == Token::CloseBracket => {
+                source.close();
+                break;
+            }
+            Ok(_) => {}
+            Err(x) => {
+                diagnostics.push(x);
+                break;
+            }
+        }
+        match parse_next(source, text_strings, diagnostics) {
+            Ok(x) => points.push(x),
+            Err(x) => {
+                diagnostics.push(x);
+                recover(source, diagnostics);
+            }
+        }
+    }
+
+    points
+}
+
+/// Parses as much of a document as possible, collecting every problem found on the way.
+pub fn parse_recovering(source: &mut VecDeque<Spanned<Token>>) -> ParseOutput {
+    let mut output = ParseOutput {
+        texts: Vec::new(),
+        components: Vec::new(),
+        ast: None,
+        diagnostics: check_brackets(source),
+    };
+    // Recovering relies on knowing where each node's children end.
+    if !output.diagnostics.is_empty() {
+        return output;
+    }
+
+    let last = match source.back() {
+        Some(x) => x.span.clone(),
+        None => Span {
+            file: Arc::from(""),
+            line: 1,
+            column: 1,
+            start: 0,
+            end: 0,
+        },
+    };
+    let mut tokens = Tokens {
+        queue: std::mem::take(source),
+        last,
+        last_token: None,
+    };
+    // Components can be defined before or after the root node.
+    let mut seen_root = false;
+    while let Some(next) = tokens.queue.front() {
+        if next.node == Token::Component {
+            match parse_component(&mut tokens, &mut output.texts, &mut output.diagnostics) {
+                Ok(x) => output.components.push(x),
+                Err(x) => {
+                    output.diagnostics.push(x);
+                    recover(&mut tokens, &mut output.diagnostics);
+                }
+            }
+            continue;
+        }
+        if seen_root {
+            // After a broken root the rest is most likely what is left of it.
+            if output.ast.is_some() {
+                output.diagnostics.push(
+                    Diagnostic::new("A document only has one root node", next.span.clone())
+                        .with_help("put the nodes inside of a div: div[ ... ]"),
+                );
+            }
+            break;
+        }
+        seen_root = true;
+        match parse_next(&mut tokens, &mut output.texts, &mut output.diagnostics) {
+            Ok(x @ ASTPoint::If(..)) | Ok(x @ ASTPoint::For(..)) => output.diagnostics.push(
+                Diagnostic::new(
+                    "The root of a document has to be a single node",
+                    x.span().clone(),
+                )
+                .with_help("put the `if` or `for` inside of a div: div[ ... ]"),
+            ),
+            Ok(x) => output.ast = Some(x),
+            Err(x) => {
+                output.diagnostics.push(x);
+                recover(&mut tokens, &mut output.diagnostics);
+            }
+        }
+    }
+    if output.ast.is_none() && output.diagnostics.is_empty() {
+        output.diagnostics.push(Diagnostic::new(
+            "The document has no root node",
+            tokens.last.clone(),
+        ));
+    }
+    output
+}
+
+/// Parses a document and expands its components.
+pub fn parse(
+    source: &mut VecDeque<Spanned<Token>>,
+) -> Result<(Vec<TextArgument>, ASTPoint), LoadError> {
+    let output = parse_recovering(source);
+    match output.ast {
+        Some(ref ast) if output.diagnostics.is_empty() => {
+            expand::expand(ast, &output.components, &output.texts).map_err(LoadError::Parse)
+        }
+        _ => Err(LoadError::Parse(output.diagnostics)),
+    }
+}
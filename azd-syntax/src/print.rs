@@ -0,0 +1,251 @@
+use crate::error::LoadError;
+use crate::parse::{self, ASTPoint, Component, NType, Node, ParseOutput, TextArgument, Value};
+use crate::span::Spanned;
+use crate::tokenize::{tokenize_with_comments, Token};
+use std::collections::VecDeque;
+use std::fmt;
+
+const INDENT: &str = "    ";
+
+/// Quotes a string so that the tokenizer reads it back unchanged.
+pub fn escape(string: &str) -> String {
+    let mut output = String::from("\"");
+    for c in string.chars() {
+        match c {
+            '\\' => output += "\\\\",
+            '"' => output += "\\\"",
+            '\t' => output += "\\t",
+            '\n' => output += "\\n",
+            x if x.is_control() => output += &format!("\\u{{{:x}}}", x as u32),
+            x => output.push(x),
+        }
+    }
+    output.push('"');
+    output
+}
+
+/// Writes syntax trees back out as canonical source: one node per line,
+/// four spaces per level, and `#id.class(arguments)` in that order.
+struct Printer<'a> {
+    texts: &'a [TextArgument],
+    /// Comments which weren't printed yet, in the order they were written.
+    comments: VecDeque<&'a Spanned<String>>,
+    /// Where the `]` of every block which wasn't printed yet is, in the order they were written.
+    closes: VecDeque<usize>,
+    output: String,
+}
+
+impl<'a> Printer<'a> {
+    fn new(texts: &'a [TextArgument], comments: &'a [Spanned<String>]) -> Printer<'a> {
+        Printer {
+            texts,
+            comments: comments.iter().collect(),
+            closes: VecDeque::new(),
+            output: String::new(),
+        }
+    }
+
+    fn with_closes(mut self, closes: VecDeque<usize>) -> Printer<'a> {
+        self.closes = closes;
+        self
+    }
+
+    fn line(&mut self, depth: usize, line: &str) {
+        for _ in 0..depth {
+            self.output += INDENT;
+        }
+        self.output += line;
+        self.output.push('\n');
+    }
+
+    /// Prints the comments which were written before byte `offset`.
+    fn comments_before(&mut self, offset: usize, depth: usize) {
+        while let Some(x) = self.comments.front() {
+            if x.span.start >= offset {
+                break;
+            }
+            let comment = format!("{{{}}}", x.node);
+            self.comments.pop_front();
+            self.line(depth, &comment);
+        }
+    }
+
+    fn text(&self, text: &TextArgument, cached: bool) -> Vec<String> {
+        // Cached texts had their `{{` and `}}` unescaped already.
+        let body = if cached {
+            text.body.replace('{', "{{").replace('}', "}}")
+        } else {
+            text.body.clone()
+        };
+        let mut arguments = vec![escape(&body)];
+        // The size can only follow the body if there is a font in between.
+        match (&text.font, text.size) {
+            (Some(font), size) => {
+                arguments.push(escape(font));
+                arguments.extend(size.map(|x| x.to_string()));
+            }
+            (None, Some(size)) => arguments.push(format!("size = {}", size)),
+            (None, None) => {}
+        }
+        arguments
+    }
+
+    fn node(&self, node: &Node) -> String {
+        let (name, mut arguments) = match node.ntype {
+            NType::Div => ("div", Vec::new()),
+            NType::Label(ref x) => ("label", vec![escape(x)]),
+            NType::Image(ref x) => ("image", vec![escape(x)]),
+            NType::Text(index) => match self.texts.get(index) {
+                Some(x) => ("text", self.text(x, true)),
+                None => ("text", vec![escape("")]),
+            },
+            NType::BoundText(ref x) => ("text", self.text(x, false)),
+        };
+        for (key, value) in &node.attributes {
+            arguments.push(format!("{} = {}", key, self::value(&value.node)));
+        }
+        // Blocks can't be written inside of `azd!`, so the style is printed as a string.
+        if !node.style.is_empty() {
+            let style: Vec<String> = node
+                .style
+                .iter()
+                .map(|x| format!("{}: {}", x.node.property, x.node.value))
+                .collect();
+            arguments.push(format!("style = {}", escape(&style.join("; "))));
+        }
+        let mut output = name.to_string();
+        if let Some(ref x) = node.id {
+            output += &format!("#{}", x);
+        }
+        for x in &node.classes {
+            output += &format!(".{}", x);
+        }
+        // Labels, images and texts always have their parentheses.
+        if !arguments.is_empty() || name != "div" {
+            output += &format!("({})", arguments.join(", "));
+        }
+        output
+    }
+
+    fn block(&mut self, depth: usize, head: &str, body: &[ASTPoint]) {
+        self.line(depth, &format!("{}[", head));
+        for i in body {
+            self.point(depth + 1, i);
+        }
+        // Comments after the last child stay inside of the block.
+        if let Some(x) = self.closes.pop_front() {
+            self.comments_before(x, depth + 1);
+        }
+        self.line(depth, "]");
+    }
+
+    fn point(&mut self, depth: usize, point: &ASTPoint) {
+        self.comments_before(point.span().start, depth);
+        match point {
+            ASTPoint::Element(x) => {
+                let node = self.node(x);
+                self.line(depth, &format!("{};", node));
+            }
+            ASTPoint::Joint(x, body) => {
+                let node = self.node(x);
+                self.block(depth, &node, body);
+            }
+            ASTPoint::If(x, body) => {
+                let not = if x.negated { "!" } else { "" };
+                self.block(depth, &format!("if({}{})", not, x.name), body);
+            }
+            ASTPoint::For(x, body) => {
+                self.block(depth, &format!("for({} in {})", x.item, x.list), body);
+            }
+            ASTPoint::Use(x) => {
+                if x.arguments.is_empty() {
+                    self.line(depth, &format!("{};", x.name));
+                } else {
+                    let arguments: Vec<String> =
+                        x.arguments.iter().map(|x| value(&x.node)).collect();
+                    self.line(depth, &format!("{}({});", x.name, arguments.join(", ")));
+                }
+            }
+            ASTPoint::Include(x) => self.line(depth, &format!("include({});", escape(&x.path))),
+        }
+    }
+
+    fn component(&mut self, component: &Component) {
+        self.comments_before(component.span.start, 0);
+        let head = if component.parameters.is_empty() {
+            format!("component {}", component.name)
+        } else {
+            format!(
+                "component {}({})",
+                component.name,
+                component.parameters.join(", ")
+            )
+        };
+        self.block(0, &head, &component.body);
+    }
+
+    /// Prints the components and the root node in the order they were written,
+    /// separated by blank lines.
+    fn document(mut self, components: &[Component], ast: Option<&ASTPoint>) -> String {
+        let mut items: Vec<(usize, Option<&Component>)> =
+            components.iter().map(|x| (x.span.start, Some(x))).collect();
+        if let Some(x) = ast {
+            items.push((x.span().start, None));
+        }
+        items.sort_by_key(|x| x.0);
+        for (index, (_, item)) in items.into_iter().enumerate() {
+            if index > 0 {
+                self.output.push('\n');
+            }
+            match (item, ast) {
+                (Some(x), _) => self.component(x),
+                (None, Some(x)) => self.point(0, x),
+                (None, None) => {}
+            }
+        }
+        self.comments_before(usize::MAX, 0);
+        self.output
+    }
+}
+
+fn value(value: &Value) -> String {
+    match value {
+        Value::Str(x) => escape(x),
+        Value::Num(x) => x.to_string(),
+        Value::Bool(x) => x.to_string(),
+        Value::Ident(x) => x.clone(),
+    }
+}
+
+/// Prints a syntax tree, such as one from `load::load_document`, as source.
+pub fn to_azd(texts: &[TextArgument], ast: &ASTPoint) -> String {
+    Printer::new(texts, &[]).document(&[], Some(ast))
+}
+
+/// Reformats a document into its canonical form, optionally keeping its comments.
+/// Components and includes are kept as they were written.
+pub fn format(source: &str, file: &str, keep_comments: bool) -> Result<String, LoadError> {
+    let (tokens, comments) = tokenize_with_comments(source, file)?;
+    // Blocks are printed in the order they were written, so they close in that order too.
+    let closes = tokens
+        .iter()
+        .filter(|x| x.node == Token::CloseBracket)
+        .map(|x| x.span.start)
+        .collect();
+    let output = parse::parse_recovering(&mut VecDeque::from(tokens));
+    if !output.diagnostics.is_empty() {
+        return Err(LoadError::Parse(output.diagnostics));
+    }
+    let comments = if keep_comments { &comments[..] } else { &[] };
+    Ok(Printer::new(&output.texts, comments)
+        .with_closes(closes)
+        .document(&output.components, output.ast.as_ref()))
+}
+
+/// Prints the document without its comments, which the parser doesn't keep.
+impl fmt::Display for ParseOutput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let printer = Printer::new(&self.texts, &[]);
+        f.write_str(&printer.document(&self.components, self.ast.as_ref()))
+    }
+}
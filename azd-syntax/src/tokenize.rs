@@ -0,0 +1,577 @@
+use crate::diagnostics::Diagnostic;
+use crate::error::LoadError;
+use crate::span::{Span, Spanned};
+use std::fmt;
+use std::sync::Arc;
+use unicode_xid::UnicodeXID;
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Token {
+    Div,
+    Label,
+    Image,
+    Text,
+    If,
+    For,
+    In,
+    Component,
+    Include,
+    Semicolon,
+    Equals,
+    Comma,
+    Not,
+    OpenBracket,
+    CloseBracket,
+    OpenParen,
+    CloseParen,
+    Num(Number),
+    Id(String),
+    Class(String),
+    Ident(String),
+    Str(String),
+    /// The contents of a `{ property: value; }` block right after a node.
+    Style(String),
+}
+
+/// The unit after a number, as in CSS.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Unit {
+    Px,
+    Pt,
+    Em,
+    Percent,
+}
+
+impl Unit {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Unit::Px => "px",
+            Unit::Pt => "pt",
+            Unit::Em => "em",
+            Unit::Percent => "%",
+        }
+    }
+}
+
+/// A number such as `12`, `-4`, `10.5pt` or `50%`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Number {
+    pub value: f32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unit: Option<Unit>,
+}
+
+impl Number {
+    /// A number without a unit.
+    pub fn new(value: f32) -> Number {
+        Number { value, unit: None }
+    }
+
+    /// The number as a count or an index, if it is a whole number without a unit.
+    /// Numbers from 2^24 up are left out, since not all of them fit in an `f32`
+    /// and one which was written there may have been rounded.
+    pub fn index(&self) -> Option<usize> {
+        let exact = 0.0..16_777_216.0;
+        if self.unit.is_none() && exact.contains(&self.value) && self.value.fract() == 0.0 {
+            Some(self.value as usize)
+        } else {
+            None
+        }
+    }
+}
+
+/// Written the way it is in a document, so `12px` stays `12px`.
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)?;
+        match self.unit {
+            Some(x) => f.write_str(x.as_str()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Part of a name.
+const WORD: u8 = 1;
+/// Can start a name.
+const START: u8 = 2;
+const DIGIT: u8 = 4;
+const SPACE: u8 = 8;
+
+/// What each ASCII character can be part of, so classifying one is a single lookup.
+static CLASSES: [u8; 128] = {
+    let mut classes = [0; 128];
+    let mut byte = 0;
+    while byte < 128 {
+        let c = byte as u8;
+        if c.is_ascii_alphabetic() || c == b'-' || c == b'_' {
+            classes[byte] = WORD | START;
+        } else if c.is_ascii_digit() {
+            classes[byte] = WORD | DIGIT;
+        } else if c == b' ' || c == b'\t' || c == b'\r' || c == b'\n' {
+            classes[byte] = SPACE;
+        }
+        byte += 1;
+    }
+    classes
+};
+
+/// Where a token starts: its byte offset, line and column.
+type Position = (usize, usize, usize);
+
+/// Names follow the Unicode XID rules, and may contain `-` as well.
+/// Any Unicode whitespace separates tokens, as does a byte order mark.
+fn is(c: char, class: u8) -> bool {
+    let classes = if c.is_ascii() {
+        CLASSES[c as usize]
+    } else if c.is_whitespace() || c == '\u{feff}' {
+        SPACE
+    } else if c.is_xid_start() {
+        WORD | START
+    } else if c.is_xid_continue() {
+        WORD
+    } else {
+        0
+    };
+    classes & class != 0
+}
+
+/// Streams the tokens of a document, tagging each with where it was found.
+/// `{comments}` are skipped, and kept for `comments` unless they are dropped.
+///
+/// The first error ends the stream.
+pub struct Lexer<'a> {
+    source: &'a str,
+    file: Arc<str>,
+    /// Where the next token is looked for, as a byte offset, line and column.
+    position: usize,
+    line: usize,
+    column: usize,
+    comments: Comments,
+    keep_comments: bool,
+    failed: bool,
+    /// Where a `{` opens a style block rather than a comment: right after a node's
+    /// type or selectors, with no space in between.
+    style_at: Option<usize>,
+}
+
+impl<'a> Lexer<'a> {
+    /// `file` is only used to label the spans.
+    pub fn new(source: &'a str, file: &str) -> Lexer<'a> {
+        Lexer {
+            source,
+            file: file.into(),
+            position: 0,
+            line: 1,
+            column: 1,
+            comments: Comments::new(),
+            keep_comments: true,
+            failed: false,
+            style_at: None,
+        }
+    }
+
+    /// Skips comments without keeping them.
+    pub fn without_comments(mut self) -> Lexer<'a> {
+        self.keep_comments = false;
+        self
+    }
+
+    /// The comments which were passed so far.
+    pub fn comments(&self) -> &[Spanned<String>] {
+        &self.comments
+    }
+
+    pub fn into_comments(self) -> Comments {
+        self.comments
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.position..].chars().next()
+    }
+
+    fn digit_after_sign(&self) -> bool {
+        self.source
+            .as_bytes()
+            .get(self.position + 1)
+            .is_some_and(u8::is_ascii_digit)
+    }
+
+    /// Moves past `length` bytes, keeping track of lines and columns.
+    fn advance(&mut self, length: usize) {
+        let end = (self.position + length).min(self.source.len());
+        for &byte in &self.source.as_bytes()[self.position..end] {
+            if byte == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else if byte & 0xc0 != 0x80 {
+                // Continuation bytes are part of a character which was counted already.
+                self.column += 1;
+            }
+        }
+        self.position = end;
+    }
+
+    /// Moves past every character in `class`, returning what was skipped.
+    fn advance_while(&mut self, class: u8) -> &'a str {
+        let start = self.position;
+        let rest = &self.source[start..];
+        let length = rest
+            .char_indices()
+            .find(|x| !is(x.1, class))
+            .map_or(rest.len(), |x| x.0);
+        self.advance(length);
+        &self.source[start..self.position]
+    }
+
+    /// A span from the `start` of a token, at `line` and `column`, up to here.
+    fn span(&self, start: Position) -> Span {
+        Span {
+            file: self.file.clone(),
+            line: start.1,
+            column: start.2,
+            start: start.0,
+            end: self.position,
+        }
+    }
+
+    /// An error about the character at the `start` of a token.
+    fn error(&mut self, start: Position, message: String, help: Option<&str>) -> LoadError {
+        self.failed = true;
+        let length = self.source[start.0..]
+            .chars()
+            .next()
+            .map_or(0, |x| x.len_utf8());
+        let span = Span {
+            end: start.0 + length,
+            ..self.span(start)
+        };
+        let diagnostic = Diagnostic::new(message, span);
+        LoadError::Lex(match help {
+            Some(x) => diagnostic.with_help(x),
+            None => diagnostic,
+        })
+    }
+
+    fn comment(&mut self, start: Position) -> Result<(), LoadError> {
+        let length = match self.source[self.position..].find('}') {
+            Some(x) => x,
+            None => {
+                return Err(self.error(
+                    start,
+                    "Unexpected end of input in a comment".to_string(),
+                    Some("comments are closed with `}`: {like this}"),
+                ))
+            }
+        };
+        let contents = &self.source[self.position + 1..self.position + length];
+        self.advance(length + 1);
+        if self.keep_comments {
+            let span = self.span(start);
+            self.comments.push(Spanned::new(contents.to_string(), span));
+        }
+        Ok(())
+    }
+
+    /// Reads a style block, whose braces can nest for `{parameters}`.
+    /// Braces inside of CSS strings, like `content: "}"`, are left alone.
+    fn style(&mut self, start: Position) -> Result<Token, LoadError> {
+        let mut depth = 0;
+        let mut quote = None;
+        let mut escaped = false;
+        for (index, &byte) in self.source.as_bytes()[self.position..].iter().enumerate() {
+            match (quote, byte) {
+                (Some(_), _) if escaped => escaped = false,
+                (Some(_), b'\\') => escaped = true,
+                (Some(x), y) if x == y => quote = None,
+                (Some(_), _) => {}
+                (None, b'"') | (None, b'\'') => quote = Some(byte),
+                (None, b'{') => depth += 1,
+                (None, b'}') if depth > 1 => depth -= 1,
+                (None, b'}') => {
+                    let contents = &self.source[self.position + 1..self.position + index];
+                    self.advance(index + 1);
+                    return Ok(Token::Style(contents.to_string()));
+                }
+                (None, _) => {}
+            }
+        }
+        Err(self.error(
+            start,
+            "Unexpected end of input in a style block".to_string(),
+            Some("style blocks are closed with `}`: div{ width: 100px; }"),
+        ))
+    }
+
+    fn string(&mut self, start: Position) -> Result<Token, LoadError> {
+        self.advance(1);
+        let mut contents = String::new();
+        let mut escaped_quote = false;
+        loop {
+            // Everything up to the next quote or escape is taken as it is.
+            let rest = &self.source[self.position..];
+            let length = rest.find(&['"', '\\'][..]).unwrap_or(rest.len());
+            contents += &rest[..length];
+            self.advance(length);
+            match self.peek() {
+                Some('"') => {
+                    self.advance(1);
+                    return Ok(Token::Str(contents));
+                }
+                Some(_) => {
+                    let escape = (self.position, self.line, self.column);
+                    self.advance(1);
+                    let c = match self.source[self.position..].chars().next() {
+                        Some('t') => '\t',
+                        Some('n') => '\n',
+                        Some('\\') => '\\',
+                        Some('"') => {
+                            escaped_quote = true;
+                            '"'
+                        }
+                        Some('u') => {
+                            contents.push(self.unicode_escape(escape)?);
+                            continue;
+                        }
+                        Some(x) => {
+                            self.advance(x.len_utf8());
+                            self.failed = true;
+                            return Err(LoadError::Lex(
+                                Diagnostic::new(
+                                    format!("Found an invalid escape character `{}`", x),
+                                    self.span(escape),
+                                )
+                                .with_help(
+                                    "the valid escapes are \\t, \\n, \\\\, \\\" and \\u{1F600}",
+                                ),
+                            ));
+                        }
+                        None => continue,
+                    };
+                    contents.push(c);
+                    self.advance(1);
+                }
+                None => {
+                    let help = if escaped_quote {
+                        "an escaped quote `\\\"` doesn't close the text"
+                    } else {
+                        "text is closed with `\"`: \"like this\""
+                    };
+                    return Err(self.error(
+                        start,
+                        "Unexpected end of input in a text".to_string(),
+                        Some(help),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Reads a number, its sign and decimals, and its unit.
+    fn number(&mut self, start: Position) -> Result<Token, LoadError> {
+        if self.peek().is_some_and(|x| !is(x, DIGIT)) {
+            self.advance(1);
+        }
+        self.advance_while(DIGIT);
+        // A point only belongs to the number if digits follow it.
+        let rest = &self.source.as_bytes()[self.position..];
+        if rest.len() > 1 && rest[0] == b'.' && rest[1].is_ascii_digit() {
+            self.advance(1);
+            self.advance_while(DIGIT);
+        }
+        let digits = &self.source[start.0..self.position];
+        let unit = if self.peek() == Some('%') {
+            self.advance(1);
+            Some(Unit::Percent)
+        } else {
+            match self.advance_while(WORD) {
+                "" => None,
+                "px" => Some(Unit::Px),
+                "pt" => Some(Unit::Pt),
+                "em" => Some(Unit::Em),
+                x => {
+                    let message = format!("Unknown unit `{}`", x);
+                    return Err(self.invalid_number(
+                        start,
+                        message,
+                        "numbers can end in px, pt, em or %",
+                    ));
+                }
+            }
+        };
+        match digits.parse::<f32>() {
+            Ok(value) if value.is_finite() => Ok(Token::Num(Number { value, unit })),
+            _ => {
+                let message = format!("Number `{}` is too large", digits);
+                Err(self.invalid_number(start, message, "numbers can be up to about 3.4e38"))
+            }
+        }
+    }
+
+    /// An error about the whole number which starts at `start`.
+    fn invalid_number(&mut self, start: Position, message: String, help: &str) -> LoadError {
+        self.failed = true;
+        LoadError::Lex(Diagnostic::new(message, self.span(start)).with_help(help))
+    }
+
+    /// Reads the rest of a `\\u{...}` escape, which starts at `escape`.
+    fn unicode_escape(&mut self, escape: Position) -> Result<char, LoadError> {
+        let rest = &self.source[self.position..];
+        // At most six digits, so a missing `}` doesn't swallow the rest of the text.
+        let length = match rest.find('}') {
+            Some(x) if rest.starts_with("u{") && x <= 8 => x + 1,
+            _ if rest.starts_with("u{") => 2,
+            _ => 1,
+        };
+        let digits = &rest[..length];
+        self.advance(length);
+        let c = digits
+            .strip_prefix("u{")
+            .and_then(|x| x.strip_suffix('}'))
+            .filter(|x| !x.is_empty() && x.len() <= 6)
+            .and_then(|x| u32::from_str_radix(x, 16).ok())
+            .and_then(std::char::from_u32);
+        c.ok_or_else(|| {
+            self.failed = true;
+            LoadError::Lex(
+                Diagnostic::new(
+                    format!("Invalid unicode escape `\\{}`", digits),
+                    self.span(escape),
+                )
+                .with_help("unicode escapes are up to six hex digits in braces: \\u{1F600}"),
+            )
+        })
+    }
+
+    fn next_token(&mut self) -> Option<Result<Spanned<Token>, LoadError>> {
+        loop {
+            self.advance_while(SPACE);
+            let c = self.peek()?;
+            let start = (self.position, self.line, self.column);
+            let token = match c {
+                ';' => Token::Semicolon,
+                '=' => Token::Equals,
+                ',' => Token::Comma,
+                '!' => Token::Not,
+                '(' => Token::OpenParen,
+                ')' => Token::CloseParen,
+                '[' => Token::OpenBracket,
+                ']' => Token::CloseBracket,
+                '{' if self.style_at == Some(self.position) => {
+                    return Some(self.style(start).map(|x| Spanned::new(x, self.span(start))));
+                }
+                '{' => {
+                    if let Err(e) = self.comment(start) {
+                        return Some(Err(e));
+                    }
+                    continue;
+                }
+                '"' => {
+                    return Some(
+                        self.string(start)
+                            .map(|x| Spanned::new(x, self.span(start))),
+                    );
+                }
+                // `:id` and `#id` are the same, `:` is the older syntax.
+                ':' | '#' | '.' => {
+                    self.advance(1);
+                    if c == ':' {
+                        self.advance_while(SPACE);
+                    }
+                    let name = self.advance_while(WORD);
+                    if name.is_empty() {
+                        let message = format!("Expected a name after `{}`", c);
+                        let help = Some("ids and classes are written as `div#id.class`");
+                        return Some(Err(self.error(start, message, help)));
+                    }
+                    let token = if c == '.' {
+                        Token::Class(name.to_string())
+                    } else {
+                        Token::Id(name.to_string())
+                    };
+                    return Some(Ok(Spanned::new(token, self.span(start))));
+                }
+                // `-` can start a name too, but not one which starts with a digit.
+                x if is(x, DIGIT) || (x == '-' || x == '+') && self.digit_after_sign() => {
+                    return Some(
+                        self.number(start)
+                            .map(|x| Spanned::new(x, self.span(start))),
+                    );
+                }
+                x if is(x, START) => {
+                    let word = self.advance_while(WORD);
+                    let token = match word {
+                        "div" => Token::Div,
+                        "label" => Token::Label,
+                        "image" => Token::Image,
+                        "text" => Token::Text,
+                        "if" => Token::If,
+                        "for" => Token::For,
+                        "in" => Token::In,
+                        "component" => Token::Component,
+                        "include" => Token::Include,
+                        _ => Token::Ident(word.to_string()),
+                    };
+                    return Some(Ok(Spanned::new(token, self.span(start))));
+                }
+                _ => {
+                    return Some(Err(self.error(
+                        start,
+                        format!("Unexpected character {:?}", c),
+                        None,
+                    )));
+                }
+            };
+            self.advance(1);
+            return Some(Ok(Spanned::new(token, self.span(start))));
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Spanned<Token>, LoadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let token = self.next_token();
+        self.style_at = match token {
+            Some(Ok(ref x)) => match x.node {
+                Token::Div
+                | Token::Label
+                | Token::Image
+                | Token::Text
+                | Token::Id(_)
+                | Token::Class(_) => Some(self.position),
+                _ => None,
+            },
+            _ => None,
+        };
+        token
+    }
+}
+
+/// Splits a document into tokens, tagging each with where it was found.
+/// `file` is only used to label the spans.
+pub fn tokenize(source: &str, file: &str) -> Result<Vec<Spanned<Token>>, LoadError> {
+    Lexer::new(source, file).without_comments().collect()
+}
+
+/// The contents of a document's `{comments}`, which aren't tokens.
+pub type Comments = Vec<Spanned<String>>;
+
+/// Like `tokenize`, but also returns the comments, for the printer.
+pub fn tokenize_with_comments(
+    source: &str,
+    file: &str,
+) -> Result<(Vec<Spanned<Token>>, Comments), LoadError> {
+    let mut lexer = Lexer::new(source, file);
+    let tokens = lexer
+        .by_ref()
+        .collect::<Result<Vec<Spanned<Token>>, LoadError>>()?;
+    Ok((tokens, lexer.into_comments()))
+}
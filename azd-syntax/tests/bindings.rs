@@ -0,0 +1,79 @@
+extern crate azul_document_loader_syntax;
+
+mod common;
+
+use azul_document_loader_syntax::bindings::*;
+use azul_document_loader_syntax::load::load_from_str;
+use azul_document_loader_syntax::*;
+use std::collections::{HashMap, VecDeque};
+
+#[test]
+fn templates_are_filled_in() {
+    let mut values = HashMap::new();
+    values.insert("counter".to_string(), "5".to_string());
+    assert_eq!(
+        interpolate("Count: {counter}", &values),
+        Ok("Count: 5".to_string())
+    );
+    assert_eq!(
+        interpolate("{{counter}} { counter }", &values),
+        Ok("{counter} 5".to_string())
+    );
+    assert!(interpolate("{missing}", &values).is_err());
+    assert!(interpolate("{counter", &values).is_err());
+}
+
+#[test]
+fn texts_with_bindings_are_not_cached() {
+    let tokens = tokenize::tokenize(
+        "div[ text (\"Count: {counter}\"); text (\"{{fixed}}\"); ]",
+        "test.azd",
+    )
+    .unwrap();
+    let (texts, ast) = parse::parse(&mut VecDeque::from(tokens)).unwrap();
+    assert_eq!(texts.len(), 1);
+    assert_eq!(texts[0].body, "{fixed}");
+    match ast {
+        parse::ASTPoint::Joint(_, ref body) => match body[0] {
+            parse::ASTPoint::Element(parse::Node {
+                ntype: parse::NType::BoundText(ref x),
+                ..
+            }) => assert_eq!(x.body, "Count: {counter}"),
+            ref x => panic!("Expected a bound text, got {:?}", x),
+        },
+        x => panic!("Expected a joint, got {:?}", x),
+    }
+}
+
+#[test]
+fn malformed_templates_are_found_when_loading() {
+    assert_eq!(check_template("Count: {counter} {{literal}} }"), Ok(()));
+    assert!(check_template("{").is_err());
+    assert!(check_template("{ }").is_err());
+    let cases = [
+        ("div[ label(\"{\"); ]", "Unclosed `{`", "\"{\""),
+        ("div[ label(\"a {b\"); ]", "Unclosed `{b`", "\"a {b\""),
+        (
+            "div[ text(\"{}\"); ]",
+            "`{}` doesn't name a binding",
+            "\"{}\"",
+        ),
+        (
+            "div[ text(body = \"x {y\", size = 12); ]",
+            "Unclosed `{y`",
+            "body = \"x {y\"",
+        ),
+        (
+            "component card(title)[ label(\"{title}\"); ]\ndiv[ card(\"{\"); ]",
+            "Unclosed `{`",
+            "\"{\"",
+        ),
+    ];
+    common::assert_diagnostics(&cases);
+    for &(source, _, _) in &cases {
+        let x = common::first_diagnostic(source);
+        assert_eq!(x.help.as_deref(), Some(TEMPLATE_HELP), "{}", source);
+    }
+    load_from_str("component card(title)[ label(\"{title}\"); ]\ndiv[ card(\"{name}\"); ]")
+        .unwrap();
+}
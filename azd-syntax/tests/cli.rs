@@ -0,0 +1,88 @@
+extern crate azul_document_loader_syntax;
+
+use std::process::Command;
+
+fn azd(args: &[&str]) -> (bool, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_azd"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn check_reports_problems() {
+    let (ok, _, errors) = azd(&["check", "tests/test.azd", "tests/include/main.azd"]);
+    assert!(ok, "{}", errors);
+    let (ok, _, errors) = azd(&["check", "tests/include/broken.azd"]);
+    assert!(!ok);
+    assert!(errors.contains("error: Labels take a string"), "{}", errors);
+    let (ok, _, _) = azd(&["frobnicate"]);
+    assert!(!ok);
+}
+
+#[test]
+fn fmt_checks_and_rewrites() {
+    let path = std::env::temp_dir().join(format!("azd-fmt-{}.azd", std::process::id()));
+    let file = path.to_str().unwrap();
+    std::fs::write(&path, "div:a[label(\"x\");]").unwrap();
+    assert!(!azd(&["fmt", "--check", file]).0);
+    assert!(azd(&["fmt", file]).0);
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "div#a[\n    label(\"x\");\n]\n"
+    );
+    assert!(azd(&["fmt", "--check", file]).0);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn dump_prints_json() {
+    use azul_document_loader_syntax::{load, print};
+
+    let (ok, tokens, _) = azd(&["dump", "tokens", "tests/include/parts/logo.azd"]);
+    assert!(ok);
+    assert!(
+        tokens.starts_with("[{\"node\":\"Text\",\"span\":{"),
+        "{}",
+        tokens
+    );
+    assert!(tokens.contains("{\"node\":{\"Id\":\"logo\"}"), "{}", tokens);
+    let (ok, ast, _) = azd(&["dump", "ast", "tests/include/main.azd"]);
+    assert!(ok);
+    assert!(ast.starts_with("{\"texts\":[{\"body\":"), "{}", ast);
+    // What is dumped loads back as the same document.
+    let (texts, tree) = load::load_from_json(&ast).unwrap();
+    let (expected_texts, expected_tree) = load::load_document("tests/include/main.azd").unwrap();
+    assert_eq!(texts, expected_texts);
+    assert_eq!(
+        print::to_azd(&texts, &tree),
+        print::to_azd(&expected_texts, &expected_tree)
+    );
+}
+
+#[cfg(not(feature = "serde"))]
+#[test]
+fn dump_needs_serde() {
+    let (ok, _, errors) = azd(&["dump", "ast", "tests/include/main.azd"]);
+    assert!(!ok);
+    assert!(errors.contains("`serde` feature"), "{}", errors);
+}
+
+#[test]
+fn compile_writes_compiled_documents() {
+    let path = std::env::temp_dir().join(format!("azd-compile-{}.azdc", std::process::id()));
+    let output = path.to_str().unwrap();
+    assert!(azd(&["compile", "tests/include/main.azd", output]).0);
+    let bytes = std::fs::read(&path).unwrap();
+    assert!(bytes.starts_with(b"AZDC"));
+    std::fs::remove_file(&path).unwrap();
+    assert!(!azd(&["compile", "tests/include/broken.azd", output]).0);
+    assert!(!path.exists());
+}
@@ -0,0 +1,34 @@
+//! What the tests share. Each of them is a crate of its own, using only part of it.
+#![allow(dead_code)]
+
+use azul_document_loader_syntax::diagnostics::Diagnostic;
+use azul_document_loader_syntax::load::load_from_str;
+
+/// The first diagnostic loading `source` fails with.
+pub fn first_diagnostic(source: &str) -> Diagnostic {
+    match load_from_str(source) {
+        Err(e) if !e.diagnostics().is_empty() => e.diagnostics()[0].clone(),
+        x => panic!("Expected a diagnostic for {:?}, got {:?}", source, x),
+    }
+}
+
+/// Checks that loading each `(source, message, at)` fails with a diagnostic whose
+/// message starts with `message`, pointing at `at` in the source.
+pub fn assert_diagnostics(cases: &[(&str, &str, &str)]) {
+    for &(source, message, at) in cases {
+        let x = first_diagnostic(source);
+        assert!(
+            x.message.starts_with(message),
+            "{:?}: {}",
+            source,
+            x.message
+        );
+        assert_eq!(
+            &source[x.span.start..x.span.end],
+            at,
+            "{:?}: {}",
+            source,
+            x.message
+        );
+    }
+}
@@ -0,0 +1,76 @@
+extern crate azul_document_loader_syntax;
+
+use azul_document_loader_syntax::compiled::load_compiled;
+use azul_document_loader_syntax::error::LoadError;
+use azul_document_loader_syntax::load::load_from_str;
+use azul_document_loader_syntax::*;
+
+const DOCUMENT: &str = r#"
+div#root.page{ width: 100px; }(tabindex = 2, on_click = save)[
+    text("Hello", "sans-serif", 10.5pt, line_height = 1.4, align = center);
+    text("{name}'s page");
+    if(!hidden)[
+        label("Shown");
+    ]
+    for(item in items)[
+        image("icon.png", draggable = true);
+    ]
+]
+"#;
+
+fn decode_error(bytes: &[u8]) -> String {
+    match load_compiled(bytes) {
+        Err(LoadError::Decode(x)) => x,
+        x => panic!("Expected a decode error, got {:?}", x),
+    }
+}
+
+#[test]
+fn compiled_documents_round_trip() {
+    let (texts, ast) = load_from_str(DOCUMENT).unwrap();
+    let bytes = compiled::compile(&texts, &ast);
+    assert!(bytes.starts_with(b"AZDC"));
+    let (loaded_texts, loaded_ast) = load_compiled(&bytes).unwrap();
+    assert_eq!(loaded_texts, texts);
+    assert_eq!(
+        print::to_azd(&loaded_texts, &loaded_ast),
+        print::to_azd(&texts, &ast)
+    );
+    assert_eq!(loaded_ast.span(), ast.span());
+    // Compiling is deterministic, so compiled documents can be checked in.
+    assert_eq!(compiled::compile(&loaded_texts, &loaded_ast), bytes);
+}
+
+#[test]
+fn stale_and_corrupt_documents_are_rejected() {
+    let (texts, ast) = load_from_str(DOCUMENT).unwrap();
+    let bytes = compiled::compile(&texts, &ast);
+
+    assert_eq!(decode_error(b"div#root;"), "not a compiled document");
+
+    let mut stale = bytes.clone();
+    stale[4] = stale[4].wrapping_add(1);
+    assert!(decode_error(&stale).contains("compile it again"));
+
+    let mut damaged = bytes.clone();
+    let last = damaged.len() - 1;
+    damaged[last] ^= 0xff;
+    assert!(decode_error(&damaged).contains("hash"));
+
+    let truncated = &bytes[..bytes.len() - 3];
+    assert!(decode_error(truncated).contains("corrupt"));
+}
+
+#[test]
+fn roots_have_to_be_single_nodes() {
+    let (texts, ast) = load_from_str(DOCUMENT).unwrap();
+    let condition = match ast {
+        parse::ASTPoint::Joint(_, body) => body[2].clone(),
+        x => panic!("Expected a joint, got {:?}", x),
+    };
+    let bytes = compiled::compile(&texts, &condition);
+    assert_eq!(
+        decode_error(&bytes),
+        "the root of a document has to be a single node"
+    );
+}
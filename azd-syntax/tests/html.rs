@@ -0,0 +1,96 @@
+extern crate azul_document_loader_syntax;
+
+use azul_document_loader_syntax::error::LoadError;
+use azul_document_loader_syntax::*;
+
+const MOCKUP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html>
+    <head><title>Mockup</title></head>
+    <body>
+        <div id="root" class="page dark">
+            <!-- The header -->
+            <div class="header">
+                <img src="logo.png" class="logo"/>
+                <span>Tom &amp; Jerry's {shop}</span>
+            </div>
+            <p>
+                Everything you
+                need &lt;3
+            </p>
+            <div></div>
+        </div>
+    </body>
+</html>
+"#;
+
+#[test]
+fn mockups_are_loaded() {
+    let (texts, ast) = load::load_named(MOCKUP, "mockup.html").unwrap();
+    assert_eq!(texts.len(), 1);
+    assert_eq!(texts[0].body, "Everything you need <3");
+    assert_eq!(
+        print::to_azd(&texts, &ast),
+        r#"div#root.page.dark[
+    div.header[
+        image.logo("logo.png");
+        label("Tom & Jerry's {{shop}}");
+    ]
+    text("Everything you need <3");
+    div;
+]
+"#
+    );
+    assert_eq!(ast.span().file.as_ref(), "mockup.html");
+    assert_eq!(ast.span().line, 6);
+}
+
+fn diagnostics(source: &str) -> Vec<String> {
+    match html::parse(source, "mockup.html") {
+        Err(LoadError::Parse(x)) => x.iter().map(|x| x.message.clone()).collect(),
+        x => panic!("Expected parse errors, got {:?}", x),
+    }
+}
+
+#[test]
+fn unsupported_markup_is_an_error() {
+    let source = r#"<div>
+    <table><tr><td>Cell</td></tr></table>
+    <div style="width: 10px"></div>
+    Loose text
+    <p>Some <b>bold</b> text</p>
+    <img/>
+</div>"#;
+    assert_eq!(
+        diagnostics(source),
+        vec![
+            "Unsupported tag `<table>`",
+            "Unsupported attribute `style` on `<div>`",
+            "Text has to be inside of a `<p>` or a `<span>`",
+            "`<p>` can only contain text",
+            "`<img>` needs a `src`",
+        ]
+    );
+    match html::parse(source, "mockup.html") {
+        Err(e) => assert!(e.render(source).contains("2 |     <table>")),
+        Ok(_) => unreachable!(),
+    }
+
+    assert_eq!(
+        diagnostics("<div><p>Text</div>"),
+        vec!["Expected `</p>`, found `</div>`"]
+    );
+    assert_eq!(
+        diagnostics("<div><img src=\"a.png\">"),
+        vec!["`<img>` is never closed"]
+    );
+    assert_eq!(diagnostics("<p>&copy;</p>"), vec!["Unknown entity `&copy`"]);
+    assert_eq!(
+        diagnostics("<div/><div/>"),
+        vec!["A document only has one root element"]
+    );
+    assert_eq!(
+        diagnostics("<!-- nothing -->"),
+        vec!["The document has no root element"]
+    );
+}
@@ -0,0 +1,141 @@
+extern crate azul_document_loader_syntax;
+
+mod common;
+
+use azul_document_loader_syntax::error::LoadError;
+use azul_document_loader_syntax::load::{load_from_reader, load_from_str};
+use azul_document_loader_syntax::*;
+use common::assert_diagnostics;
+
+macro_rules! path {
+    ($file:expr) => {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/include/", $file)
+    };
+}
+
+#[test]
+fn includes_are_spliced_in() {
+    let (texts, ast) = load::load_document(path!("main.azd")).unwrap();
+    let bodies: Vec<&str> = texts.iter().map(|x| &*x.body).collect();
+    assert_eq!(bodies, vec!["body", "title", "logo"]);
+    let body = match ast {
+        parse::ASTPoint::Joint(_, body) => body,
+        x => panic!("Expected a joint, got {:?}", x),
+    };
+    match body[0] {
+        parse::ASTPoint::Joint(ref node, ref header) => {
+            assert_eq!(node.id, Some("header".to_string()));
+            assert!(node.span.file.ends_with("parts/header.azd"));
+            match header[1] {
+                parse::ASTPoint::Element(parse::Node {
+                    ntype: parse::NType::Text(2),
+                    ..
+                }) => {}
+                ref x => panic!("Expected the logo's text, got {:?}", x),
+            }
+        }
+        ref x => panic!("Expected the header, got {:?}", x),
+    }
+    match body[1] {
+        parse::ASTPoint::Element(parse::Node {
+            ntype: parse::NType::Text(0),
+            ..
+        }) => {}
+        ref x => panic!("Expected the body's text, got {:?}", x),
+    }
+}
+
+#[test]
+fn include_cycles_are_errors() {
+    match load::load_document(path!("cycle.azd")) {
+        Err(LoadError::Included { error, .. }) => match *error {
+            LoadError::Include(ref x) => {
+                assert!(x.span.file.ends_with("parts/cycle.azd"));
+                assert_eq!(x.span.line, 2);
+            }
+            ref x => panic!("Expected an include error, got {:?}", x),
+        },
+        x => panic!("Expected an included error, got {:?}", x),
+    }
+    match load::load_document(path!("missing.azd")) {
+        Err(LoadError::Io(_)) => {}
+        x => panic!("Expected an io error, got {:?}", x),
+    }
+}
+
+#[test]
+fn errors_quote_the_included_document() {
+    let error = load::load_document(path!("broken.azd")).unwrap_err();
+    assert_eq!(error.diagnostics()[0].span.line, 2);
+    let rendered = error.render("");
+    assert!(rendered.contains("2 |     label (12);"), "{}", rendered);
+    assert!(rendered.contains("note: included from"), "{}", rendered);
+    assert!(rendered.contains("broken.azd:2:5"), "{}", rendered);
+}
+
+#[test]
+fn documents_load_from_anywhere() {
+    let source = "div[ text (\"a\"); label (\"b\"); ]";
+    let (texts, _) = load_from_str(source).unwrap();
+    assert_eq!(texts[0].body, "a");
+    let (texts, _) = load_from_reader(source.as_bytes()).unwrap();
+    assert_eq!(texts[0].body, "a");
+    match load_from_str("div[ label (12); ]") {
+        Err(LoadError::Parse(x)) => assert_eq!(&*x[0].span.file, "<string>"),
+        x => panic!("Expected a parse error, got {:?}", x),
+    }
+}
+
+#[test]
+fn attributes_are_checked_when_loading() {
+    assert_diagnostics(&[
+        (
+            "div(colour = \"red\");",
+            "Unknown attribute `colour`",
+            "colour = \"red\"",
+        ),
+        (
+            "div(tabindex = \"x\");",
+            "Invalid value for `tabindex`: Str(\"x\")",
+            "tabindex = \"x\"",
+        ),
+        (
+            "div(tabindex = -1);",
+            "Invalid value for `tabindex`",
+            "tabindex = -1",
+        ),
+        (
+            "div(tabindex = 1.5);",
+            "Invalid value for `tabindex`",
+            "tabindex = 1.5",
+        ),
+        // From 2^24 up the number may have been rounded.
+        (
+            "div(tabindex = 16777217);",
+            "Invalid value for `tabindex`",
+            "tabindex = 16777217",
+        ),
+        (
+            "div(tabindex = true);",
+            "Invalid value for `tabindex`",
+            "tabindex = true",
+        ),
+        (
+            "div(draggable = 1);",
+            "Invalid value for `draggable`",
+            "draggable = 1",
+        ),
+        ("div(class = 2);", "Invalid value for `class`", "class = 2"),
+        (
+            "div(on_click = \"increment\");",
+            "Invalid value for `on_click`",
+            "on_click = \"increment\"",
+        ),
+    ]);
+    load_from_str("div(tabindex = 16777215);").unwrap();
+    load_from_str(
+        "div(id = \"a b\", class = \"c\", tabindex = 0, draggable = false, on_click = increment)[ \
+         label(\"x\", tabindex = auto); image(\"y\", tabindex = false); ]",
+    )
+    .unwrap();
+}
@@ -0,0 +1,648 @@
+extern crate azul_document_loader_syntax;
+
+mod common;
+
+use azul_document_loader_syntax::error::LoadError;
+use azul_document_loader_syntax::*;
+use common::assert_diagnostics;
+use std::collections::VecDeque;
+
+fn load_str(source: &str) -> Result<(Vec<parse::TextArgument>, parse::ASTPoint), LoadError> {
+    let tokens = tokenize::tokenize(source, "test.azd")?;
+    parse::parse(&mut VecDeque::from(tokens))
+}
+
+#[test]
+fn bad_documents_are_errors() {
+    match load_str("div:a[ {unclosed comment ]") {
+        Err(LoadError::Lex(..)) => {}
+        x => panic!("Expected a lex error, got {:?}", x),
+    }
+    match load_str("div:a[ label:b (); ]") {
+        Err(LoadError::Parse(..)) => {}
+        x => panic!("Expected a parse error, got {:?}", x),
+    }
+    match load_str("div:a[ div:b; ") {
+        Err(LoadError::Parse(..)) => {}
+        x => panic!("Expected a parse error, got {:?}", x),
+    }
+}
+
+#[test]
+fn errors_point_at_their_source() {
+    match load_str("div:a[\n    div:b;\n    label:c (12);\n]") {
+        Err(LoadError::Parse(x)) => {
+            assert_eq!((x[0].span.line, x[0].span.column), (3, 14));
+            assert_eq!(&*x[0].span.file, "test.azd");
+        }
+        x => panic!("Expected a parse error, got {:?}", x),
+    }
+    let (_, ast) = load_str("div:a[\n    image:b (\"cat\");\n]").unwrap();
+    match ast {
+        parse::ASTPoint::Joint(_, ref body) => {
+            let span = body[0].span();
+            assert_eq!((span.line, span.column), (2, 5));
+            assert_eq!((span.start, span.end), (11, 26));
+        }
+        x => panic!("Expected a joint, got {:?}", x),
+    }
+}
+
+#[test]
+fn diagnostics_quote_the_source() {
+    let source = "div:a[\n    div:b;\n    label:c (\"one\" \"two\");\n]";
+    let error = load_str(source).unwrap_err();
+    assert_eq!(
+        error.render(source),
+        concat!(
+            "error: Labels take exactly one string\n",
+            " --> test.azd:3:20\n",
+            "  |\n",
+            "3 |     label:c (\"one\" \"two\");\n",
+            "  |                    ^^^^^\n",
+            "  |\n",
+            "  = help: labels take exactly one string: label(\"abcd\")\n",
+        )
+    );
+}
+
+#[test]
+fn lexer_streams_tokens() {
+    let source = "{ éé } div#a[\n    label(\"ö\"); ?\n    div;\n]";
+    let mut lexer = tokenize::Lexer::new(source, "test.azd");
+    let first = lexer.next().unwrap().unwrap();
+    assert_eq!(first.node, tokenize::Token::Div);
+    // Columns count characters, offsets count bytes.
+    assert_eq!((first.span.column, first.span.start), (8, 9));
+    assert_eq!(lexer.comments()[0].node, " éé ");
+
+    let rest: Vec<_> = lexer.collect();
+    let text = rest[4].as_ref().unwrap();
+    assert_eq!(text.node, tokenize::Token::Str("ö".to_string()));
+    assert_eq!((text.span.line, text.span.column), (2, 11));
+    assert_eq!(&source[text.span.start..text.span.end], "\"ö\"");
+    // The stream ends at the first error.
+    assert_eq!(rest.len(), 8);
+    match rest[7] {
+        Err(LoadError::Lex(ref x)) => assert_eq!(x.span.column, 17),
+        ref x => panic!("Expected a lex error, got {:?}", x),
+    }
+}
+
+#[test]
+fn names_and_text_can_be_unicode() {
+    let source =
+        "\u{feff}div#главная.ページ[\u{a0}label(\"Привет, \\u{1F600}\\u{d}\");\u{2028}text_ü;\n]";
+    let tokens: Vec<tokenize::Token> = tokenize::tokenize(source, "test.azd")
+        .unwrap()
+        .into_iter()
+        .map(|x| x.node)
+        .collect();
+    assert_eq!(tokens[1], tokenize::Token::Id("главная".to_string()));
+    assert_eq!(tokens[2], tokenize::Token::Class("ページ".to_string()));
+    assert_eq!(
+        tokens[6],
+        tokenize::Token::Str("Привет, \u{1F600}\r".to_string())
+    );
+    assert_eq!(tokens[9], tokenize::Token::Ident("text_ü".to_string()));
+    assert_eq!(
+        print::escape("Привет, \u{1F600}\r"),
+        "\"Привет, \u{1F600}\\u{d}\""
+    );
+
+    assert_diagnostics(&[
+        (
+            "label(\"\\u{110000}\");",
+            "Invalid unicode escape",
+            "\\u{110000}",
+        ),
+        ("label(\"a\\u{12\");", "Invalid unicode escape", "\\u{"),
+        ("div#a€;", "Unexpected character '€'", "€"),
+    ]);
+}
+
+#[test]
+fn numbers_have_signs_decimals_and_units() {
+    use tokenize::{Number, Unit};
+    let number = |value, unit| tokenize::Token::Num(Number { value, unit });
+
+    let tokens = tokenize::tokenize("12px -4 +1.5em 50% 10.5pt 0.25", "test.azd").unwrap();
+    let tokens: Vec<_> = tokens.into_iter().map(|x| x.node).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            number(12.0, Some(Unit::Px)),
+            number(-4.0, None),
+            number(1.5, Some(Unit::Em)),
+            number(50.0, Some(Unit::Percent)),
+            number(10.5, Some(Unit::Pt)),
+            number(0.25, None),
+        ]
+    );
+    let source = "component c(a, b, c, d, e, f)[ div; ]\n\
+                  div[ c(12px, -4, +1.5em, 50%, 10.5pt, 0.25); text(\"x\" \"font\" 10.5pt); ]";
+    let (texts, _) = load_str(source).unwrap();
+    assert_eq!(
+        texts[0].size,
+        Some(Number {
+            value: 10.5,
+            unit: Some(Unit::Pt)
+        })
+    );
+    assert_eq!(
+        print::format(source, "test.azd", false).unwrap(),
+        "component c(a, b, c, d, e, f)[\n    div;\n]\n\n\
+         div[\n    c(12px, -4, 1.5em, 50%, 10.5pt, 0.25);\n    text(\"x\", \"font\", 10.5pt);\n]\n"
+    );
+    // `-` only starts a number when a digit follows it.
+    assert_eq!(
+        tokenize::tokenize("-a", "test.azd").unwrap()[0].node,
+        tokenize::Token::Ident("-a".to_string())
+    );
+
+    let too_large = format!("div(a = {}0);", "9".repeat(40));
+    assert_diagnostics(&[
+        ("div(a = 12vw);", "Unknown unit `vw`", "12vw"),
+        ("div(a = 1e5);", "Unknown unit `e5`", "1e5"),
+        (&too_large, "Number `", &too_large[8..49]),
+        (
+            "div[ text(\"x\" \"font\" -2); ]",
+            "The size of a text can't be negative",
+            "-2",
+        ),
+    ]);
+}
+
+#[test]
+fn parser_reports_every_problem() {
+    let source = "div:a[\n    label:b (12);\n    div:c[\n        image:d;\n        div:e;\n    ]\n    text:f (\"x\" \"font\")\n    div:g;\n    label:h (\"ok\");\n]";
+    let tokens = tokenize::tokenize(source, "test.azd").unwrap();
+    let output = parse::parse_recovering(&mut VecDeque::from(tokens));
+    let lines: Vec<usize> = output.diagnostics.iter().map(|x| x.span.line).collect();
+    assert_eq!(lines, vec![2, 4, 8]);
+    // The broken text was dropped along with its node.
+    assert_eq!(output.texts.len(), 0);
+    match output.ast {
+        Some(parse::ASTPoint::Joint(_, ref body)) => {
+            let ids: Vec<Option<String>> = body
+                .iter()
+                .map(|x| match x {
+                    parse::ASTPoint::Element(x) | parse::ASTPoint::Joint(x, _) => x.id.clone(),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(ids, vec![Some("c".to_string()), Some("h".to_string())]);
+        }
+        ref x => panic!("Expected a joint, got {:?}", x),
+    }
+}
+
+#[test]
+fn attributes_are_collected() {
+    let (texts, ast) = load_str(
+        "div:main(class=\"a b\", tabindex=0, draggable=true)[\n    text:t (\"body\" \"font\" 12, class=\"c\");\n]",
+    )
+    .unwrap();
+    assert_eq!(texts[0].size, Some(tokenize::Number::new(12.0)));
+    match ast {
+        parse::ASTPoint::Joint(ref node, ref body) => {
+            let attributes: Vec<(&str, &parse::Value)> = node
+                .attributes
+                .iter()
+                .map(|(k, v)| (&**k, &v.node))
+                .collect();
+            assert_eq!(
+                attributes,
+                vec![
+                    ("class", &parse::Value::Str("a b".to_string())),
+                    ("draggable", &parse::Value::Bool(true)),
+                    ("tabindex", &parse::Value::Num(tokenize::Number::new(0.0))),
+                ]
+            );
+            match body[0] {
+                parse::ASTPoint::Element(ref x) => assert!(x.attributes.contains_key("class")),
+                ref x => panic!("Expected an element, got {:?}", x),
+            }
+        }
+        x => panic!("Expected a joint, got {:?}", x),
+    }
+    match load_str("div:a(class=\"x\" class=\"y\");") {
+        Err(LoadError::Parse(_)) => {}
+        x => panic!("Expected a parse error, got {:?}", x),
+    }
+}
+
+#[test]
+fn text_arguments_can_be_named() {
+    let source = "div[\n    text(size = 12pt, class = \"c\", body = \"Hi\", align = center, line_height = 1.4);\n    \
+                  text(\"Bye\", size = 10);\n]";
+    let (texts, ast) = load_str(source).unwrap();
+    assert_eq!(texts[0].body, "Hi");
+    assert_eq!(texts[0].font, None);
+    assert_eq!(
+        texts[0].size.map(|x| x.to_string()),
+        Some("12pt".to_string())
+    );
+    assert_eq!(texts[1].size, Some(tokenize::Number::new(10.0)));
+    match ast {
+        parse::ASTPoint::Joint(_, ref body) => match body[0] {
+            // Only what isn't part of the text is left as an attribute, and how it is
+            // laid out becomes its inline style.
+            parse::ASTPoint::Element(ref x) => {
+                assert_eq!(x.attributes.keys().collect::<Vec<_>>(), vec!["class"]);
+                let style: Vec<(&str, &str)> = x
+                    .style
+                    .iter()
+                    .map(|x| (&*x.node.property, &*x.node.value))
+                    .collect();
+                assert_eq!(
+                    style,
+                    vec![("line-height", "1.4"), ("text-align", "center")]
+                );
+            }
+            ref x => panic!("Expected an element, got {:?}", x),
+        },
+        ref x => panic!("Expected a joint, got {:?}", x),
+    }
+    assert_eq!(
+        print::to_azd(&texts, &ast),
+        "div[\n    text(\"Hi\", size = 12pt, class = \"c\", style = \"line-height: 1.4; text-align: center\");\n    \
+         text(\"Bye\", size = 10);\n]\n"
+    );
+
+    assert_diagnostics(&[
+        (
+            "text(\"a\", body = \"b\");",
+            "The body of the text was given twice",
+            "body = \"b\"",
+        ),
+        (
+            "text(\"a\", \"font\", font = \"b\");",
+            "The font of the text was given twice",
+            "font = \"b\"",
+        ),
+        (
+            "text(size = 12);",
+            "Texts should always have a string as their body",
+            "(size = 12)",
+        ),
+        (
+            "text(\"a\", align = middle);",
+            "Expected `left`, `center` or `right`",
+            "align = middle",
+        ),
+        (
+            "text(\"a\", line_height = -1);",
+            "The line height of a text can't be negative",
+            "line_height = -1",
+        ),
+        (
+            "text(\"a\", font = 12);",
+            "Expected the font of the text",
+            "font = 12",
+        ),
+    ]);
+}
+
+#[test]
+fn nodes_take_inline_styles() {
+    let source = "component boxed(w)[ div{ width: {w}; }; ]\n\
+                  div#a{ width: 100px;\n  background: red; }(style = \"height: 10px\", tabindex = 1)[\n    \
+                  label{color: blue}(\"x\");\n    div {just a comment};\n    boxed(\"5px\");\n]";
+    let (texts, ast) = load_str(source).unwrap();
+    let style = |x: &parse::Node| -> Vec<String> {
+        x.style
+            .iter()
+            .map(|x| format!("{}: {}", x.node.property, x.node.value))
+            .collect()
+    };
+    match ast {
+        parse::ASTPoint::Joint(ref node, ref body) => {
+            assert_eq!(
+                style(node),
+                vec!["height: 10px", "width: 100px", "background: red"]
+            );
+            assert_eq!((node.style[2].span.line, node.style[2].span.column), (3, 3));
+            assert_eq!(
+                &source[node.style[2].span.start..node.style[2].span.end],
+                "background: red"
+            );
+            assert_eq!(node.attributes.keys().collect::<Vec<_>>(), vec!["tabindex"]);
+            let styles: Vec<Vec<String>> = body
+                .iter()
+                .map(|x| match x {
+                    parse::ASTPoint::Element(x) => style(x),
+                    x => panic!("Expected an element, got {:?}", x),
+                })
+                .collect();
+            assert_eq!(
+                styles,
+                vec![vec!["color: blue"], vec![], vec!["width: 5px"]]
+            );
+        }
+        ref x => panic!("Expected a joint, got {:?}", x),
+    }
+    assert!(print::to_azd(&texts, &ast).starts_with(
+        "div#a(tabindex = 1, style = \"height: 10px; width: 100px; background: red\")[\n    \
+         label(\"x\", style = \"color: blue\");\n"
+    ));
+
+    assert_diagnostics(&[
+        (
+            "div{ width 100px };",
+            "Expected `property: value`, found `width 100px`",
+            "width 100px",
+        ),
+        (
+            "div{ : 1px };",
+            "Expected `property: value`, found `: 1px`",
+            ": 1px",
+        ),
+        (
+            "div(style = 12);",
+            "Expected a string of style declarations",
+            "style = 12",
+        ),
+        (
+            "div{ width: 1px;",
+            "Unexpected end of input in a style block",
+            "{",
+        ),
+    ]);
+
+    // Semicolons and braces in strings and parentheses are part of the value.
+    let (_, ast) =
+        load_str("div#x{ content: \"};{\"; background: url(a;b); font-family: 'a\\'}' };").unwrap();
+    match ast {
+        parse::ASTPoint::Element(ref node) => assert_eq!(
+            style(node),
+            vec![
+                "content: \"};{\"",
+                "background: url(a;b)",
+                "font-family: 'a\\'}'"
+            ]
+        ),
+        ref x => panic!("Expected an element, got {:?}", x),
+    }
+}
+
+#[test]
+fn braces_after_arguments_are_comments() {
+    let source = "component c[ div; ]\n\
+                  div[\n    label(\"x\"){note};\n    if(a){toggle}[ div; ]\n    c(){uses c};\n    \
+                  include(\"x\"){c};\n    label{a: b}{c: d}(\"y\");\n]";
+    let (tokens, comments) = tokenize::tokenize_with_comments(source, "test.azd").unwrap();
+    let comments: Vec<&str> = comments.iter().map(|x| &*x.node).collect();
+    assert_eq!(comments, vec!["note", "toggle", "uses c", "c", "c: d"]);
+    let output = parse::parse_recovering(&mut VecDeque::from(tokens));
+    assert!(output.diagnostics.is_empty(), "{:?}", output.diagnostics);
+    let formatted = print::format(source, "test.azd", true).unwrap();
+    assert!(
+        formatted.contains("    label(\"x\");\n    {note}\n    if(a)[\n"),
+        "{}",
+        formatted
+    );
+}
+
+#[test]
+fn ids_and_classes() {
+    let (_, ast) = load_str("div#header.panel.dark[\n    label:title.big (\"x\");\n]").unwrap();
+    match ast {
+        parse::ASTPoint::Joint(ref node, ref body) => {
+            assert_eq!(node.id, Some("header".to_string()));
+            assert_eq!(node.classes, vec!["panel".to_string(), "dark".to_string()]);
+            match body[0] {
+                parse::ASTPoint::Element(ref x) => {
+                    assert_eq!(x.id, Some("title".to_string()));
+                    assert_eq!(x.classes, vec!["big".to_string()]);
+                }
+                ref x => panic!("Expected an element, got {:?}", x),
+            }
+        }
+        x => panic!("Expected a joint, got {:?}", x),
+    }
+    assert_diagnostics(&[("div#a#b;", "A node can only have one `#id`", "#b")]);
+}
+
+#[test]
+fn conditions_and_loops() {
+    let (texts, ast) = load_str(
+        "div:list[\n    if(!empty)[\n        for(user in users)[\n            text (\"fixed\");\n            label (\"{user.name}\");\n        ]\n    ]\n    text (\"after\");\n]",
+    )
+    .unwrap();
+    assert_eq!(texts.len(), 2);
+    let body = match ast {
+        parse::ASTPoint::Joint(_, body) => body,
+        x => panic!("Expected a joint, got {:?}", x),
+    };
+    match body[0] {
+        parse::ASTPoint::If(ref condition, ref body) => {
+            assert_eq!((&*condition.name, condition.negated), ("empty", true));
+            match body[0] {
+                parse::ASTPoint::For(ref header, ref body) => {
+                    assert_eq!((&*header.item, &*header.list), ("user", "users"));
+                    assert_eq!(body.len(), 2);
+                }
+                ref x => panic!("Expected a for, got {:?}", x),
+            }
+        }
+        ref x => panic!("Expected an if, got {:?}", x),
+    }
+    // Texts are found by index, so repeating or skipping them can't mix them up.
+    match body[1] {
+        parse::ASTPoint::Element(parse::Node {
+            ntype: parse::NType::Text(1),
+            ..
+        }) => {}
+        ref x => panic!("Expected the second text, got {:?}", x),
+    }
+    assert_diagnostics(&[(
+        "if(x)[ div; ]",
+        "The root of a document has to be a single node",
+        "if(x)",
+    )]);
+}
+
+#[test]
+fn components_are_expanded() {
+    let (texts, ast) = load_str(concat!(
+        "component card(title, img)[\n",
+        "    div.card[\n",
+        "        label (\"{title}: {count}\");\n",
+        "        image (\"{img}\");\n",
+        "        text (\"{title}\" \"font\" 12);\n",
+        "    ]\n",
+        "]\n",
+        "div#cards[\n",
+        "    card(\"Hi {{there}}\", \"catimg\");\n",
+        "    card(\"Bye\", \"dogimg\");\n",
+        "]\n",
+    ))
+    .unwrap();
+    // Every use gets its own text, which can be cached since `title` is no binding.
+    let bodies: Vec<&str> = texts.iter().map(|x| &*x.body).collect();
+    assert_eq!(bodies, vec!["Hi {there}", "Bye"]);
+    assert_eq!(texts[1].size, Some(tokenize::Number::new(12.0)));
+    let body = match ast {
+        parse::ASTPoint::Joint(_, body) => body,
+        x => panic!("Expected a joint, got {:?}", x),
+    };
+    assert_eq!(body.len(), 2);
+    match body[1] {
+        parse::ASTPoint::Joint(ref node, ref body) => {
+            assert_eq!(node.classes, vec!["card".to_string()]);
+            let types: Vec<String> = body
+                .iter()
+                .map(|x| match x {
+                    parse::ASTPoint::Element(x) => format!("{:?}", x.ntype),
+                    x => panic!("Expected an element, got {:?}", x),
+                })
+                .collect();
+            assert_eq!(
+                types,
+                vec!["Label(\"Bye: {count}\")", "Image(\"dogimg\")", "Text(1)"]
+            );
+        }
+        ref x => panic!("Expected a joint, got {:?}", x),
+    }
+}
+
+#[test]
+fn component_parameters_are_only_names() {
+    assert_diagnostics(&[(
+        "component card(title, img = \"x\")[ div; ]\ncard(\"a\");",
+        "Parameters are only names, found `img = ...`",
+        "img = \"x\"",
+    )]);
+}
+
+#[test]
+fn recursive_components_are_rejected() {
+    let source = concat!(
+        "component a[ div[ b; ] ]\n",
+        "component b[ div[ a; ] ]\n",
+        "div[ a; ]\n",
+    );
+    match load_str(source) {
+        Err(LoadError::Parse(x)) => {
+            assert_eq!(x.len(), 1);
+            assert_eq!(x[0].message, "The component `a` uses itself");
+            assert_eq!(x[0].span.line, 2);
+            assert_eq!(&source[x[0].span.start..x[0].span.end], "a");
+        }
+        x => panic!("Expected a parse error, got {:?}", x),
+    }
+    assert_diagnostics(&[
+        (
+            "div[ missing; ]",
+            "There is no component called `missing`",
+            "missing",
+        ),
+        (
+            "component a(x)[ label(\"{x}\"); ] div[ a(\"1\", \"2\"); ]",
+            "The component `a` takes 1 argument(s) but was given 2",
+            "a(\"1\", \"2\")",
+        ),
+        (
+            "component a[ div; ] component a[ div; ] a;",
+            "The component `a` is defined more than once",
+            "component a",
+        ),
+        (
+            "div[ component a[ div; ] ]",
+            "Components can only be defined at the top of a document",
+            "component",
+        ),
+        ("div; div;", "A document only has one root node", "div"),
+    ]);
+}
+
+#[test]
+fn truncated_documents_are_errors() {
+    let source = include_str!("test.azd");
+    for (end, _) in source.char_indices() {
+        let source = &source[..end];
+        match load_str(source) {
+            Ok(_) => {}
+            Err(e) => assert!(e.render(source).starts_with("error: "), "{}", source),
+        }
+    }
+    for source in &[
+        "",
+        "   \n\t",
+        "di",
+        "div",
+        "div#",
+        "label(\"a",
+        "text(\"\\",
+        "{ a",
+        "div[",
+        "div;]",
+    ] {
+        let error = load_str(source).unwrap_err();
+        assert!(!error.render(source).is_empty());
+    }
+    assert_diagnostics(&[(
+        "div[\n    label(\"never closed);\n]",
+        "Unexpected end of input in a text",
+        "\"",
+    )]);
+}
+
+#[test]
+fn deep_and_huge_documents_are_errors() {
+    let nested = |depth: usize| "div[".repeat(depth) + "div;" + &"]".repeat(depth);
+    let (texts, ast) = load_str(&nested(parse::MAX_DEPTH)).unwrap();
+    assert!(print::to_azd(&texts, &ast).len() > parse::MAX_DEPTH);
+    assert_diagnostics(&[(
+        &nested(parse::MAX_DEPTH + 1),
+        "Nodes are nested more than 128 levels deep",
+        "[",
+    )]);
+
+    // Each component doubles the size of the one before it.
+    let mut source = "component a0[ div; ]\n".to_string();
+    for i in 1..40 {
+        source += &format!("component a{}[ a{}; a{}; ]\n", i, i - 1, i - 1);
+    }
+    source += "div[ a39; ]";
+    assert_diagnostics(&[(
+        &source,
+        "The document expands to more than 1048576 nodes",
+        "a20",
+    )]);
+}
+
+#[test]
+fn broken_children_are_not_empty_lists() {
+    let messages = |source: &str| -> Vec<String> {
+        let tokens = tokenize::tokenize(source, "test.azd").unwrap();
+        let output = parse::parse_recovering(&mut VecDeque::from(tokens));
+        // Nodes which fail after their text was read leave no text behind.
+        assert_eq!(output.texts.len(), 0, "{}", source);
+        output.diagnostics.into_iter().map(|x| x.message).collect()
+    };
+    for source in &[
+        "div[ label(12); ]",
+        "div[ if(a)[ label(12); ] ]",
+        "div[ text(\"x\", style = 1); ]",
+        "div[ text{ color }(\"x\"); ]",
+    ] {
+        let messages = messages(source);
+        assert_eq!(messages.len(), 1, "{}: {:?}", source, messages);
+        assert!(
+            !messages[0].contains("Empty list"),
+            "{}: {:?}",
+            source,
+            messages
+        );
+    }
+    assert_eq!(
+        messages("div[ div[] ]"),
+        vec!["Empty list of children".to_string()]
+    );
+    assert_eq!(
+        messages("div[ if(a)[] ]"),
+        vec!["Empty list of children".to_string()]
+    );
+}
@@ -0,0 +1,76 @@
+extern crate azul_document_loader_syntax;
+
+use azul_document_loader_syntax::load::load_from_str;
+use azul_document_loader_syntax::*;
+
+const MESSY: &str = "{header}\ncomponent card(title)[ label.t ( \"{title}\" ) ; ]\n\
+div:a  (tabindex=0 , class = \"x\")[text(\"say \\\"hi\\\"\\n{{ok}}\" \"font\" 12);\n\
+  {inner} if(!b)[card(\"c\");] include(\"part.azd\");\n]";
+
+#[test]
+fn documents_are_printed_canonically() {
+    let formatted = print::format(MESSY, "test.azd", true).unwrap();
+    assert_eq!(
+        formatted,
+        concat!(
+            "{header}\n",
+            "component card(title)[\n",
+            "    label.t(\"{title}\");\n",
+            "]\n",
+            "\n",
+            "div#a(class = \"x\", tabindex = 0)[\n",
+            "    text(\"say \\\"hi\\\"\\n{{ok}}\", \"font\", 12);\n",
+            "    {inner}\n",
+            "    if(!b)[\n",
+            "        card(\"c\");\n",
+            "    ]\n",
+            "    include(\"part.azd\");\n",
+            "]\n",
+        )
+    );
+    // Formatting is stable, and comments are only kept when asked for.
+    assert_eq!(
+        print::format(&formatted, "test.azd", true).unwrap(),
+        formatted
+    );
+    assert!(!print::format(MESSY, "test.azd", false)
+        .unwrap()
+        .contains("{inner}"));
+}
+
+#[test]
+fn printed_trees_parse_back_the_same() {
+    let (texts, ast) = load_from_str(MESSY.replace("include(\"part.azd\");", "").as_str()).unwrap();
+    let printed = print::to_azd(&texts, &ast);
+    let (texts_again, ast_again) = load_from_str(&printed).unwrap();
+    assert_eq!(texts, texts_again);
+    assert_eq!(printed, print::to_azd(&texts_again, &ast_again));
+    assert!(printed.contains("label.t(\"c\");"));
+}
+
+#[test]
+fn trailing_comments_stay_in_their_block() {
+    let source = "div[ div[ div; {a} ] {b} if(c)[ div; {d} ] ] {e}";
+    let formatted = print::format(source, "test.azd", true).unwrap();
+    assert_eq!(
+        formatted,
+        concat!(
+            "div[\n",
+            "    div[\n",
+            "        div;\n",
+            "        {a}\n",
+            "    ]\n",
+            "    {b}\n",
+            "    if(c)[\n",
+            "        div;\n",
+            "        {d}\n",
+            "    ]\n",
+            "]\n",
+            "{e}\n",
+        )
+    );
+    assert_eq!(
+        print::format(&formatted, "test.azd", true).unwrap(),
+        formatted
+    );
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "serde")]
+
+extern crate azul_document_loader_syntax;
+
+use azul_document_loader_syntax::error::LoadError;
+use azul_document_loader_syntax::load::load_from_str;
+use azul_document_loader_syntax::*;
+
+const DOCUMENT: &str = r#"
+component card(title)[
+    div.card[
+        label("{title}");
+    ]
+]
+
+div#root[
+    text("Hello", "sans-serif", 12);
+    if(!hidden)[
+        card("First");
+    ]
+    for(item in items)[
+        image("icon.png", tabindex = 1);
+    ]
+]
+"#;
+
+#[test]
+fn trees_round_trip_through_json_and_ron() {
+    let (texts, ast) = load_from_str(DOCUMENT).unwrap();
+    let expected = print::to_azd(&texts, &ast);
+    let encoded = load::Encoded { texts, ast };
+
+    let json = serde_json::to_string(&encoded).unwrap();
+    let (texts, ast) = load::load_from_json(&json).unwrap();
+    assert_eq!(print::to_azd(&texts, &ast), expected);
+
+    let ron = ron::to_string(&encoded).unwrap();
+    let (texts, ast) = load::load_from_ron(&ron).unwrap();
+    assert_eq!(print::to_azd(&texts, &ast), expected);
+}
+
+#[test]
+fn encoded_trees_are_checked() {
+    // Spans and everything else that's optional can be left out.
+    let json = r#"{
+        "texts": [{ "body": "Hello", "font": null, "size": null }],
+        "ast": { "Joint": [{ "ntype": "Div", "id": "root" }, [
+            { "Element": { "ntype": { "Text": 0 } } },
+            { "Element": { "ntype": { "Label": "World" }, "classes": ["big"] } }
+        ]] }
+    }"#;
+    let (texts, ast) = load::load_from_json(json).unwrap();
+    assert_eq!(texts.len(), 1);
+    assert_eq!(
+        print::to_azd(&texts, &ast),
+        "div#root[\n    text(\"Hello\");\n    label.big(\"World\");\n]\n"
+    );
+
+    let missing = r#"{ "ast": { "Element": { "ntype": { "Text": 3 } } } }"#;
+    match load::load_from_json(missing) {
+        Err(LoadError::Decode(x)) => assert!(x.contains("text 3"), "{}", x),
+        x => panic!("Expected a decode error, got {:?}", x),
+    }
+
+    let component = r#"(ast: Use((name: "card")))"#;
+    match load::load_from_ron(component) {
+        Err(LoadError::Decode(x)) => assert!(x.contains("`card`"), "{}", x),
+        x => panic!("Expected a decode error, got {:?}", x),
+    }
+
+    match load::load_from_json("{ \"ast\": ") {
+        Err(LoadError::Decode(_)) => {}
+        x => panic!("Expected a decode error, got {:?}", x),
+    }
+}
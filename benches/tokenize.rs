@@ -0,0 +1,42 @@
+extern crate azul_document_loader;
+
+use azul_document_loader::{load_from_str, tokenize};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+/// A document of about `size` bytes, with as many strings, comments and nested
+/// nodes as real layouts have.
+fn document(size: usize) -> String {
+    let mut output = String::from("{ generated }\ndiv#root.page[\n");
+    let mut index = 0;
+    while output.len() < size {
+        output += &format!(
+            "    div#row-{0}.row(tabindex = {0})[\n        \
+                 {{ row {0} }}\n        \
+                 label(\"Row {0}: \\\"quoted\\\" and escaped\\ttext\");\n        \
+                 text(\"{{{{Body}}}} of row {0}\", \"sans-serif\", 12);\n        \
+                 if(!hidden)[ image(\"icon-{0}.png\", draggable = true); ]\n    \
+             ]\n",
+            index
+        );
+        index += 1;
+    }
+    output += "]\n";
+    output
+}
+
+fn benches(c: &mut Criterion) {
+    for &megabytes in &[1, 4] {
+        let source = document(megabytes << 20);
+        let mut group = c.benchmark_group(format!("{}MB", megabytes));
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.sample_size(10);
+        group.bench_function("tokenize", |b| {
+            b.iter(|| tokenize::tokenize(&source, "bench.azd").unwrap())
+        });
+        group.bench_function("load", |b| b.iter(|| load_from_str(&source).unwrap()));
+        group.finish();
+    }
+}
+
+criterion_group!(tokenizer, benches);
+criterion_main!(tokenizer);
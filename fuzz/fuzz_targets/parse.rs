@@ -0,0 +1,20 @@
+#![no_main]
+
+use azul_document_loader_syntax::{parse, print, tokenize};
+use libfuzzer_sys::fuzz_target;
+
+/// Parses without `load_from_str`, so includes aren't looked for on disk.
+fn load(source: &str) -> Result<(), String> {
+    let tokens = tokenize::tokenize(source, "fuzz.azd").map_err(|e| e.render(source))?;
+    let (texts, ast) = parse::parse(&mut tokens.into()).map_err(|e| e.render(source))?;
+    // Whatever parses prints back out as a document which parses the same.
+    let printed = print::to_azd(&texts, &ast);
+    let tokens = tokenize::tokenize(&printed, "printed.azd").unwrap();
+    let (reparsed_texts, reparsed) = parse::parse(&mut tokens.into()).unwrap();
+    assert_eq!(print::to_azd(&reparsed_texts, &reparsed), printed);
+    Ok(())
+}
+
+fuzz_target!(|source: &str| {
+    let _ = load(source);
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use azul_document_loader_syntax::tokenize::Lexer;
+use libfuzzer_sys::fuzz_target;
+
+// Every input is either tokens or an error which can be shown.
+fuzz_target!(|source: &str| {
+    for token in Lexer::new(source, "fuzz.azd") {
+        match token {
+            Ok(x) => assert!(x.span.start <= x.span.end && x.span.end <= source.len()),
+            Err(e) => {
+                e.render(source);
+            }
+        }
+    }
+});
@@ -0,0 +1,117 @@
+use azul::prelude::*;
+use std::collections::HashMap;
+
+/// The Rust callbacks a document can attach to its nodes by name:
+///
+/// ```text
+/// div:button(on_click=increment);
+/// ```
+pub struct CallbackRegistry<T: Layout> {
+    callbacks: HashMap<String, Callback<T>>,
+}
+
+impl<T: Layout> CallbackRegistry<T> {
+    pub fn new() -> CallbackRegistry<T> {
+        CallbackRegistry {
+            callbacks: HashMap::new(),
+        }
+    }
+
+    /// Registers `callback` under `name`, replacing any callback registered before it.
+    pub fn insert<S: Into<String>>(&mut self, name: S, callback: Callback<T>) {
+        self.callbacks.insert(name.into(), callback);
+    }
+
+    pub fn with_callback<S: Into<String>>(mut self, name: S, callback: Callback<T>) -> Self {
+        self.insert(name, callback);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Callback<T>> {
+        self.callbacks.get(name)
+    }
+}
+
+impl<T: Layout> Default for CallbackRegistry<T> {
+    fn default() -> Self {
+        CallbackRegistry::new()
+    }
+}
+
+/// The event an `on_*` attribute listens for, if it is one.
+pub(crate) fn event_for_attribute(attribute: &str) -> Option<On> {
+    Some(match attribute {
+        "on_click" | "on_mouse_up" => On::MouseUp,
+        "on_mouse_down" => On::MouseDown,
+        "on_mouse_over" => On::MouseOver,
+        "on_mouse_enter" => On::MouseEnter,
+        "on_mouse_leave" => On::MouseLeave,
+        "on_scroll" => On::Scroll,
+        "on_text_input" => On::TextInput,
+        "on_key_down" => On::VirtualKeyDown,
+        "on_key_up" => On::VirtualKeyUp,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::EVENT_ATTRIBUTES;
+
+    struct Model;
+
+    impl Layout for Model {
+        fn layout(&self, _: WindowInfo<Self>) -> Dom<Self> {
+            Dom::new(NodeType::Div)
+        }
+    }
+
+    fn redraw(_: &mut AppState<Model>, _: WindowEvent<Model>) -> UpdateScreen {
+        UpdateScreen::Redraw
+    }
+
+    fn keep(_: &mut AppState<Model>, _: WindowEvent<Model>) -> UpdateScreen {
+        UpdateScreen::DontRedraw
+    }
+
+    /// Whether `callback` calls `function`.
+    fn is(
+        callback: Option<&Callback<Model>>,
+        function: fn(&mut AppState<Model>, WindowEvent<Model>) -> UpdateScreen,
+    ) -> bool {
+        callback.is_some_and(|x| x.0 as usize == function as usize)
+    }
+
+    #[test]
+    fn callbacks_are_found_by_name() {
+        let mut callbacks = CallbackRegistry::new().with_callback("redraw", Callback(redraw));
+        callbacks.insert("keep", Callback(keep));
+        assert!(is(callbacks.get("redraw"), redraw));
+        assert!(is(callbacks.get("keep"), keep));
+        assert!(callbacks.get("missing").is_none());
+        assert!(CallbackRegistry::<Model>::default().get("redraw").is_none());
+    }
+
+    #[test]
+    fn later_callbacks_replace_earlier_ones() {
+        let callbacks = CallbackRegistry::new()
+            .with_callback("update", Callback(redraw))
+            .with_callback("update", Callback(keep));
+        assert!(is(callbacks.get("update"), keep));
+    }
+
+    #[test]
+    fn every_event_attribute_has_an_event() {
+        for x in EVENT_ATTRIBUTES {
+            assert!(event_for_attribute(x).is_some(), "{}", x);
+        }
+        assert!(matches!(event_for_attribute("on_click"), Some(On::MouseUp)));
+        assert!(matches!(
+            event_for_attribute("on_key_down"),
+            Some(On::VirtualKeyDown)
+        ));
+        assert!(event_for_attribute("on_hover").is_none());
+        assert!(event_for_attribute("tabindex").is_none());
+    }
+}
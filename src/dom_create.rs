@@ -1,8 +1,13 @@
 extern crate azul;
 
 use azul::prelude::*;
+use crate::bindings::{interpolate, Bindings, Record, Scope, TEMPLATE_HELP};
+use crate::callbacks::{event_for_attribute, CallbackRegistry};
+use crate::diagnostics::Diagnostic;
+use crate::error::LoadError;
 use crate::parse::*;
-use std::collections::VecDeque;
+use crate::span::{Span, Spanned};
+use std::collections::{BTreeMap, VecDeque};
 
 macro_rules! relate {
     ($head:expr, $($child:expr),+) => {
@@ -34,12 +39,12 @@ macro_rules! label {
     };
 }
 macro_rules! image {
-    ($find:expr, $imgid:expr) => {
-        Dom::new(NodeType::Image($find.resources.get_image($imgid).unwrap()))
+    ($imgid:expr) => {
+        Dom::new(NodeType::Image($imgid))
     };
 
-    ($find:expr, $imgid:expr, $id:expr) => {
-        Dom::new(NodeType::Image($find.resources.get_image($imgid).unwrap())).with_id($id)
+    ($imgid:expr, $id:expr) => {
+        Dom::new(NodeType::Image($imgid)).with_id($id)
     };
 }
 macro_rules! text {
@@ -52,67 +57,237 @@ macro_rules! text {
     }};
 }
 
+/// Fills `template` in from `bindings`, for the node at `span`.
+pub fn fill_in(
+    template: &str,
+    bindings: &dyn Bindings,
+    span: impl Fn() -> Span,
+) -> Result<String, LoadError> {
+    interpolate(template, bindings)
+        .map_err(|x| LoadError::Dom(Diagnostic::new(x, span()).with_help(TEMPLATE_HELP)))
+}
+
+/// An image node showing the image `create_resources` loaded as `name`.
+pub fn image<T>(name: &str, info: &WindowInfo<T>, span: impl Fn() -> Span) -> Result<Dom<T>, LoadError>
+where
+    T: Layout,
+{
+    let image = info.resources.get_image(name.to_string()).ok_or_else(|| {
+        LoadError::Dom(
+            Diagnostic::new(format!("Image {:?} was never loaded", name), span())
+                .with_help("images have to be passed to `create_resources` first"),
+        )
+    })?;
+    Ok(image!(image))
+}
+
+/// A text node showing the `index`th of the texts `create_resources` cached.
+pub fn text<T>(index: usize, texts: &VecDeque<TextId>, span: impl Fn() -> Span) -> Result<Dom<T>, LoadError>
+where
+    T: Layout,
+{
+    let t_id = texts.get(index).ok_or_else(|| {
+        LoadError::Dom(Diagnostic::new(
+            "More text nodes in the document than created texts",
+            span(),
+        ))
+    })?;
+    Ok(text!(*t_id))
+}
+
+/// Attaches the callback registered as `name` to the event of the `on_*` `attribute`.
+pub fn callback<T>(
+    dom: Dom<T>,
+    attribute: &str,
+    name: &str,
+    callbacks: &CallbackRegistry<T>,
+    span: impl Fn() -> Span,
+) -> Result<Dom<T>, LoadError>
+where
+    T: Layout,
+{
+    let event = event_for_attribute(attribute).ok_or_else(|| {
+        LoadError::Dom(Diagnostic::new(
+            format!("`{}` doesn't attach a callback", attribute),
+            span(),
+        ))
+    })?;
+    match callbacks.get(name) {
+        Some(x) => Ok(dom.with_callback(event, x.clone())),
+        None => Err(LoadError::Dom(
+            Diagnostic::new(format!("No callback named `{}` was registered", name), span())
+                .with_help("callbacks have to be added to the `CallbackRegistry` given to `consume_ast`"),
+        )),
+    }
+}
+
+/// Applies a declaration of a node's inline style as a dynamic CSS override,
+/// named after its property.
+pub fn style<T>(
+    dom: Dom<T>,
+    property: &str,
+    value: &str,
+    span: impl Fn() -> Span,
+) -> Result<Dom<T>, LoadError>
+where
+    T: Layout,
+{
+    let parsed = ParsedCssProperty::from_kv(property, value).map_err(|e| {
+        LoadError::Dom(
+            Diagnostic::new(format!("Invalid style `{}: {}`", property, value), span())
+                .with_help(format!("{:?}", e)),
+        )
+    })?;
+    Ok(dom.with_css_override(property.to_string(), parsed))
+}
+
+/// The records a `for` loop at `span` goes through.
+pub fn list(bindings: &dyn Bindings, name: &str, span: impl Fn() -> Span) -> Result<Vec<Record>, LoadError> {
+    bindings.list(name).ok_or_else(|| {
+        LoadError::Dom(
+            Diagnostic::new(format!("No list is bound to `{}`", name), span())
+                .with_help("lists come from `Bindings::list`"),
+        )
+    })
+}
+
+fn apply_attributes<T>(
+    mut dom: Dom<T>,
+    attributes: &BTreeMap<String, Spanned<Value>>,
+    callbacks: &CallbackRegistry<T>,
+) -> Result<Dom<T>, LoadError>
+where
+    T: Layout,
+{
+    for (key, value) in attributes {
+        // Trees which weren't parsed, like ones loaded from JSON, haven't been checked yet.
+        check_attribute(key, value).map_err(LoadError::Dom)?;
+        dom = match (&**key, &value.node) {
+            ("id", Value::Str(x)) => x.split_whitespace().fold(dom, |dom, x| dom.with_id(x)),
+            ("class", Value::Str(x)) => x.split_whitespace().fold(dom, |dom, x| dom.with_class(x)),
+            ("tabindex", Value::Num(x)) => match x.index() {
+                Some(x) => dom.with_tab_index(TabIndex::OverrideInParent(x)),
+                None => dom,
+            },
+            ("tabindex", Value::Ident(_)) => dom.with_tab_index(TabIndex::Auto),
+            ("tabindex", Value::Bool(_)) => dom.with_tab_index(TabIndex::NoKeyboardFocus),
+            ("draggable", Value::Bool(x)) => dom.is_draggable(*x),
+            // `check_attribute` only lets identifiers through for `on_*` attributes.
+            (_, Value::Ident(name)) => callback(dom, key, name, callbacks, || value.span.clone())?,
+            _ => dom,
+        };
+    }
+    Ok(dom)
+}
+
+fn apply_style<T>(dom: Dom<T>, declarations: &[Spanned<Declaration>]) -> Result<Dom<T>, LoadError>
+where
+    T: Layout,
+{
+    declarations.iter().try_fold(dom, |dom, x| {
+        style(dom, &x.node.property, &x.node.value, || x.span.clone())
+    })
+}
+
 fn create_single<T>(
-    point: &NType,
-    id: &Option<String>,
-    texts: &mut VecDeque<TextId>,
-    info: &WindowInfo<T>
-) -> Dom<T>
+    node: &Node,
+    texts: &VecDeque<TextId>,
+    info: &WindowInfo<T>,
+    callbacks: &CallbackRegistry<T>,
+    bindings: &dyn Bindings,
+) -> Result<Dom<T>, LoadError>
 where
     T: Layout,
 {
-    match point {
-        NType::Div => {
-            if let Some(ref x) = id {
-                div!(x.clone())
-            } else {
-                div!()
-            }
-        }
-        NType::Label(ref text) => {
-            if let Some(ref x) = id {
-                label!(text.clone(), x.clone())
-            } else {
-                label!(text.clone())
-            }
-        }
-        NType::Image(ref imgid) => {
-            if let Some(ref x) = id {
-                image!(info, imgid.clone(), x.clone())
-            } else {
-                Dom::new(NodeType::Image(
-                    info.resources.get_image(imgid.clone()).unwrap(),
-                ))
+    let span = || node.span.clone();
+    let dom = match node.ntype {
+        NType::Div => div!(),
+        NType::Label(ref text) => label!(fill_in(text, bindings, span)?),
+        NType::BoundText(ref text) => label!(fill_in(&text.body, bindings, span)?),
+        NType::Image(ref imgid) => image(imgid, info, span)?,
+        NType::Text(index) => text(index, texts, span)?,
+    };
+    let dom = match node.id {
+        Some(ref x) => dom.with_id(x.clone()),
+        None => dom,
+    };
+    let dom = node.classes.iter().fold(dom, |dom, x| dom.with_class(x.clone()));
+    let dom = apply_attributes(dom, &node.attributes, callbacks)?;
+    let dom = match node.ntype {
+        NType::BoundText(ref x) => x
+            .declarations()
+            .iter()
+            .try_fold(dom, |dom, x| style(dom, &x.property, &x.value, span))?,
+        _ => dom,
+    };
+    apply_style(dom, &node.style)
+}
+
+/// Adds the children in `body` to `parent`, evaluating any `if`s and `for`s among them.
+fn add_children<T>(
+    mut parent: Dom<T>,
+    body: &[ASTPoint],
+    texts: &VecDeque<TextId>,
+    info: &WindowInfo<T>,
+    callbacks: &CallbackRegistry<T>,
+    bindings: &dyn Bindings,
+) -> Result<Dom<T>, LoadError>
+where
+    T: Layout,
+{
+    for i in body {
+        parent = match i {
+            ASTPoint::If(ref condition, ref body) => {
+                if bindings.is_true(&condition.name) != condition.negated {
+                    add_children(parent, body, texts, info, callbacks, bindings)?
+                } else {
+                    parent
+                }
             }
-        }
-        NType::Text => {
-            let t_id = texts.pop_front().unwrap();
-            if let Some(ref x) = id {
-                text!(t_id, x.clone())
-            } else {
-                text!(t_id)
+            ASTPoint::For(ref header, ref body) => {
+                for record in &list(bindings, &header.list, || header.span.clone())? {
+                    let scope = Scope {
+                        parent: bindings,
+                        item: &header.item,
+                        record,
+                    };
+                    parent = add_children(parent, body, texts, info, callbacks, &scope)?;
+                }
+                parent
             }
-        }
+            x => relate![parent, create_dom(x, texts, info, callbacks, bindings)?],
+        };
     }
+    Ok(parent)
 }
 
 pub fn create_dom<T>(
     head: &ASTPoint,
-    texts: &mut VecDeque<TextId>,
-    info: &WindowInfo<T>
-) -> Dom<T>
+    texts: &VecDeque<TextId>,
+    info: &WindowInfo<T>,
+    callbacks: &CallbackRegistry<T>,
+    bindings: &dyn Bindings,
+) -> Result<Dom<T>, LoadError>
 where
     T: Layout,
 {
     match head {
-        //node.1 is an Id
-        ASTPoint::Element(ref node) => create_single(&node.0, &node.1, texts, info),
+        ASTPoint::Element(ref node) => create_single(node, texts, info, callbacks, bindings),
         ASTPoint::Joint(ref head, ref body) => {
-            let mut main_node = create_single(&head.0, &head.1, texts, info);
-            for i in body {
-                main_node = relate![main_node, create_dom(i, texts, info)];
-            }
-            main_node
+            let main_node = create_single(head, texts, info, callbacks, bindings)?;
+            add_children(main_node, body, texts, info, callbacks, bindings)
         }
+        ASTPoint::If(..) | ASTPoint::For(..) => Err(LoadError::Dom(
+            Diagnostic::new("An `if` or `for` can't be turned into a single node", head.span().clone())
+                .with_help("put it inside of a div: div[ ... ]"),
+        )),
+        ASTPoint::Use(ref x) => Err(LoadError::Dom(
+            Diagnostic::new(format!("The component `{}` was never expanded", x.name), x.span.clone())
+                .with_help("syntax trees from `parse::parse` have their components expanded"),
+        )),
+        ASTPoint::Include(ref x) => Err(LoadError::Dom(
+            Diagnostic::new(format!("{:?} was never included", x.path), x.span.clone())
+                .with_help("includes are resolved by `load_resources`"),
+        )),
     }
 }
@@ -0,0 +1,80 @@
+//! Documents which `azd!` parsed at compile time.
+
+use crate::bindings::Bindings;
+use crate::callbacks::CallbackRegistry;
+use crate::error::LoadError;
+use crate::parse::TextArgument;
+use std::collections::VecDeque;
+use std::fmt;
+
+// What the code `azd!` generates refers to, so that it doesn't depend on
+// the app's own dependencies.
+#[doc(hidden)]
+pub use azul::prelude::{Dom, Layout, NodeType, TabIndex, TextId, WindowInfo};
+
+/// Builds the `Dom` of a document, like `consume_ast` does with its syntax tree.
+pub type BuildDom<T> = fn(
+    &VecDeque<TextId>,
+    &WindowInfo<T>,
+    &CallbackRegistry<T>,
+    &dyn Bindings,
+) -> Result<Dom<T>, LoadError>;
+
+/// A document which was turned into Rust code while the app was compiled, so neither
+/// its source nor its syntax tree are left for runtime:
+///
+/// ```text
+/// let layout = azd!("layout.azd");
+/// let texts = create_resources(&mut app, layout.texts().into(), &fonts, images)?;
+/// ```
+///
+/// and in `Layout::layout`:
+///
+/// ```text
+/// layout.dom(&self.texts, &info, &callbacks, &bindings)
+/// ```
+pub struct Embedded<T: Layout> {
+    texts: fn() -> Vec<TextArgument>,
+    dom: BuildDom<T>,
+}
+
+impl<T: Layout> Embedded<T> {
+    /// Only meant to be called by the code `azd!` generates.
+    #[doc(hidden)]
+    pub const fn new(texts: fn() -> Vec<TextArgument>, dom: BuildDom<T>) -> Embedded<T> {
+        Embedded { texts, dom }
+    }
+
+    /// The texts to pass to `create_resources`, in the order the document uses them.
+    pub fn texts(&self) -> Vec<TextArgument> {
+        (self.texts)()
+    }
+
+    /// Builds the document's `Dom`, with the ids `create_resources` made from `texts()`.
+    pub fn dom(
+        &self,
+        texts: &VecDeque<TextId>,
+        info: &WindowInfo<T>,
+        callbacks: &CallbackRegistry<T>,
+        bindings: &dyn Bindings,
+    ) -> Result<Dom<T>, LoadError> {
+        (self.dom)(texts, info, callbacks, bindings)
+    }
+}
+
+// Not derived, since those would need `T` to implement them too.
+impl<T: Layout> Clone for Embedded<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Layout> Copy for Embedded<T> {}
+
+impl<T: Layout> fmt::Debug for Embedded<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Embedded")
+            .field("texts", &self.texts())
+            .finish()
+    }
+}
@@ -3,11 +3,32 @@ extern crate azul;
 use azul::prelude::*;
 use std::collections::{HashMap, VecDeque};
 use std::ops::DerefMut;
+use crate::tokenize::{Number, Unit};
 
+pub use crate::bindings::{BindingContext, Bindings};
+pub use crate::callbacks::CallbackRegistry;
+pub use crate::compiled::load_compiled;
+pub use crate::embedded::Embedded;
+pub use crate::error::LoadError;
+pub use crate::load::{load_from_reader, load_from_str};
+pub use azul_document_loader_macro::azd;
+pub use azul_document_loader_syntax::{
+    bindings, compiled, diagnostics, error, expand, html, load, parse, print, span, tokenize,
+};
+
+pub mod callbacks;
 pub mod dom_create;
-pub mod load;
-pub mod parse;
-pub mod tokenize;
+pub mod embedded;
+pub mod reload;
+
+/// Embeds a document in the binary, resolving `$file` like `include_str!` does,
+/// and loads it. Nothing is read from disk, so the document can't have includes.
+#[macro_export]
+macro_rules! include_azd {
+    ($file:expr) => {
+        $crate::load::load_embedded(include_str!($file), $file)
+    };
+}
 
 ///
 /// load_text
@@ -15,23 +36,38 @@ pub mod tokenize;
 /// consume_ast
 /// 
 
-pub fn load_resources(filename: &str) -> (Vec<parse::TextArgument>, parse::ASTPoint) {
-    let source = load::load_into_string(filename).unwrap();
-    let tokens = tokenize::tokenize(&*source);
-    parse::parse(&mut VecDeque::from(tokens))
+pub fn load_resources(
+    filename: &str,
+) -> Result<(Vec<parse::TextArgument>, parse::ASTPoint), LoadError> {
+    load::load_document(filename)
 }
 
-fn create_fonts<T>(app: &mut App<T>, fonts: &Vec<(Box<[u8]>, &str)>) -> HashMap<String, FontId>
+fn create_fonts<T>(
+    app: &mut App<T>,
+    fonts: &Vec<(Box<[u8]>, &str)>,
+) -> Result<HashMap<String, FontId>, LoadError>
 where
     T: Layout,
 {
     let mut new_fonts = HashMap::<String, FontId>::new();
     for (bytes, name) in fonts {
         let fontid = FontId::ExternalFont(name.to_string());
-        app.add_font(fontid.clone(), &mut &**bytes).unwrap();
+        app.add_font(fontid.clone(), &mut &**bytes).map_err(|e| {
+            LoadError::Resource(format!("Could not load font {:?}: {:?}", name, e))
+        })?;
         new_fonts.insert(name.to_string(), fontid);
     }
-    new_fonts
+    Ok(new_fonts)
+}
+
+/// Numbers without a unit are in pixels.
+fn pixel_value(size: Number) -> PixelValue {
+    match size.unit {
+        None | Some(Unit::Px) => PixelValue::px(size.value),
+        Some(Unit::Pt) => PixelValue::pt(size.value),
+        Some(Unit::Em) => PixelValue::em(size.value),
+        Some(Unit::Percent) => PixelValue::percent(size.value),
+    }
 }
 
 pub fn create_resources<T>(
@@ -39,42 +75,55 @@ pub fn create_resources<T>(
     mut strings: VecDeque<parse::TextArgument>,
     preloaded_fonts: &Vec<(Box<[u8]>, &str)>,
     images: Vec<(String, &mut Box<[u8]>, ImageType)>
-) -> VecDeque<TextId>
+) -> Result<VecDeque<TextId>, LoadError>
 where
     T: Layout,
 {
-    let fonts = create_fonts(app, &preloaded_fonts);
+    let fonts = create_fonts(app, &preloaded_fonts)?;
     let mut ids = VecDeque::<TextId>::new();
     let default = FontId::BuiltinFont("sans-serif".into());
     while let Some(t) = strings.pop_front() {
-        let fontid;
-        if let Some(ref x) = t.font{
-            if !fonts.contains_key(x) {
-                panic!("You forgot to load in font {:?}", x);
-            }
-            fontid = fonts.get(&t.font.unwrap()).unwrap();
-        }
-        else {
-            fontid = &default;
-        }
-        let fontsize = t.size.unwrap_or(10) as f32;
-        ids.push_back(app.add_text_cached(t.body, &fontid, PixelValue::px(fontsize), None));
+        let fontid = match t.font {
+            Some(ref x) => fonts.get(x).ok_or_else(|| {
+                LoadError::Resource(format!("You forgot to load in font {:?}", x))
+            })?,
+            None => &default,
+        };
+        let fontsize = pixel_value(t.size.unwrap_or_else(|| Number::new(10.0)));
+        ids.push_back(app.add_text_cached(t.body, &fontid, fontsize, None));
     }
     for (name, data, imgtype) in images{
-        app.add_image(name, &mut&**data, imgtype).unwrap();
+        app.add_image(name.clone(), &mut&**data, imgtype).map_err(|e| {
+            LoadError::Resource(format!("Could not load image {:?}: {:?}", name, e))
+        })?;
     }
-    (
-        ids
-    )
+    Ok(ids)
 }
 
 pub fn consume_ast<T>(
     syntax_tree: &parse::ASTPoint,
     texts: &VecDeque<TextId>,
     info_source: &WindowInfo<T>,
-) -> Dom<T>
+    callbacks: &CallbackRegistry<T>,
+    bindings: &dyn Bindings,
+) -> Result<Dom<T>, LoadError>
+where
+    T: Layout,
+{
+    dom_create::create_dom(syntax_tree, texts, info_source, callbacks, bindings)
+}
+
+/// Builds the `Dom` of the current version of a document which is being reloaded.
+/// `texts` are the ids `create_resources` made from `document.texts()` at startup.
+pub fn consume_document<T>(
+    document: &reload::Document,
+    texts: &VecDeque<TextId>,
+    info_source: &WindowInfo<T>,
+    callbacks: &CallbackRegistry<T>,
+    bindings: &dyn Bindings,
+) -> Result<Dom<T>, LoadError>
 where
     T: Layout,
 {
-    dom_create::create_dom(syntax_tree, &mut texts.clone(), info_source)
+    consume_ast(&document.cached_ast(), texts, info_source, callbacks, bindings)
 }
@@ -0,0 +1,224 @@
+use crate::error::LoadError;
+use crate::load::{load_document, load_into_string};
+use crate::parse::{ASTPoint, NType, Node, TextArgument};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// When a file was last changed, and how long it was then.
+type Stamp = Option<(SystemTime, u64)>;
+
+/// A document which is loaded again whenever one of its files changes.
+///
+/// Clones are handles to the same document, so one can be kept in the app's data model
+/// and read from `Layout::layout` while a background thread keeps it up to date.
+#[derive(Clone)]
+pub struct Document {
+    state: Arc<Mutex<State>>,
+}
+
+struct State {
+    filename: String,
+    texts: Vec<TextArgument>,
+    ast: Arc<ASTPoint>,
+    /// The texts of the first version, which are the ones the app could cache.
+    cached: Arc<Vec<TextArgument>>,
+    /// `ast` with the texts which changed since the first version uncached.
+    cached_ast: Arc<ASTPoint>,
+    version: usize,
+    error: Option<String>,
+    stamps: Vec<(PathBuf, Stamp)>,
+}
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Every file a tree was loaded from, found through its spans.
+fn files(point: &ASTPoint, found: &mut Vec<PathBuf>) {
+    let file = PathBuf::from(&*point.span().file);
+    if !found.contains(&file) {
+        found.push(file);
+    }
+    match point {
+        ASTPoint::Joint(_, body) | ASTPoint::If(_, body) | ASTPoint::For(_, body) => {
+            for i in body {
+                files(i, found);
+            }
+        }
+        ASTPoint::Element(_) | ASTPoint::Use(_) | ASTPoint::Include(_) => {}
+    }
+}
+
+/// Every file a document at `filename` depends on, along with its current stamp.
+fn watched(filename: &str, ast: &ASTPoint, error: Option<&LoadError>) -> Vec<(PathBuf, Stamp)> {
+    let mut watched = vec![PathBuf::from(filename)];
+    files(ast, &mut watched);
+    // A broken include might not be part of the tree yet.
+    for x in error.map_or(&[][..], |x| x.diagnostics()) {
+        let file = PathBuf::from(&*x.span.file);
+        if !watched.contains(&file) {
+            watched.push(file);
+        }
+    }
+    watched
+        .into_iter()
+        .map(|x| {
+            let stamp = stamp(&x);
+            (x, stamp)
+        })
+        .collect()
+}
+
+impl Document {
+    /// Loads the document at `filename`, then checks its files for changes every `interval`
+    /// for as long as any handle to it is left.
+    pub fn watch(filename: &str, interval: Duration) -> Result<Document, LoadError> {
+        let (texts, ast) = load_document(filename)?;
+        let ast = Arc::new(ast);
+        let state = State {
+            filename: filename.to_string(),
+            stamps: watched(filename, &ast, None),
+            cached: Arc::new(texts.clone()),
+            texts,
+            cached_ast: ast.clone(),
+            ast,
+            version: 0,
+            error: None,
+        };
+        let document = Document {
+            state: Arc::new(Mutex::new(state)),
+        };
+
+        let weak: Weak<Mutex<State>> = Arc::downgrade(&document.state);
+        thread::Builder::new()
+            .name(format!("reload {}", filename))
+            .spawn(move || loop {
+                thread::sleep(interval);
+                match weak.upgrade() {
+                    Some(state) => {
+                        Document { state }.reload();
+                    }
+                    None => break,
+                }
+            })?;
+        Ok(document)
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // A panic elsewhere doesn't make the last tree any less usable.
+        self.state.lock().unwrap_or_else(|x| x.into_inner())
+    }
+
+    /// Loads the document again if any of its files changed, returning whether it did.
+    /// When the new version has problems the last good tree is kept, and `error` tells
+    /// what they are.
+    pub fn reload(&self) -> bool {
+        let (filename, stamps, cached, ast) = {
+            let state = self.state();
+            (
+                state.filename.clone(),
+                state.stamps.clone(),
+                state.cached.clone(),
+                state.ast.clone(),
+            )
+        };
+        if stamps.iter().all(|(path, old)| stamp(path) == *old) {
+            return false;
+        }
+        // Layouts keep using the last version while the new one is loaded, so the
+        // state is only locked to swap it in.
+        match load_document(&filename) {
+            Ok((texts, ast)) => {
+                let stamps = watched(&filename, &ast, None);
+                let cached_ast = Arc::new(uncache(&ast, &texts, &cached));
+                let mut state = self.state();
+                state.cached_ast = cached_ast;
+                state.texts = texts;
+                state.ast = Arc::new(ast);
+                state.version += 1;
+                state.error = None;
+                state.stamps = stamps;
+            }
+            Err(e) => {
+                let rendered = match load_into_string(&filename) {
+                    Ok(source) => e.render(&source),
+                    Err(_) => format!("error: {}\n", e),
+                };
+                let stamps = watched(&filename, &ast, Some(&e));
+                let mut state = self.state();
+                state.error = Some(rendered);
+                state.stamps = stamps;
+            }
+        }
+        true
+    }
+
+    /// How many times the document was reloaded successfully.
+    pub fn version(&self) -> usize {
+        self.state().version
+    }
+
+    /// The problems with the latest change, rendered along with the source, if it
+    /// couldn't be loaded. The app can show them until the next change fixes them.
+    pub fn error(&self) -> Option<String> {
+        self.state().error.clone()
+    }
+
+    /// The texts of the current version.
+    pub fn texts(&self) -> Vec<TextArgument> {
+        self.state().texts.clone()
+    }
+
+    /// The syntax tree of the current version.
+    pub fn ast(&self) -> Arc<ASTPoint> {
+        self.state().ast.clone()
+    }
+
+    /// The syntax tree of the current version, for use with the text ids which were
+    /// created from the first version's texts: the app can't cache texts while it runs,
+    /// so texts which changed since are laid out like labels until it is restarted.
+    ///
+    /// It is only made once per version, so this is cheap enough to call on every layout.
+    pub fn cached_ast(&self) -> Arc<ASTPoint> {
+        self.state().cached_ast.clone()
+    }
+}
+
+fn uncache(point: &ASTPoint, texts: &[TextArgument], cached: &[TextArgument]) -> ASTPoint {
+    let node = |node: &Node| match node.ntype {
+        NType::Text(index) if cached.get(index) != texts.get(index) => {
+            let mut text = texts[index].clone();
+            // The body was already unescaped, so it mustn't be taken for bindings now.
+            text.body = text.body.replace('{', "{{").replace('}', "}}");
+            Node {
+                ntype: NType::BoundText(text),
+                ..node.clone()
+            }
+        }
+        _ => node.clone(),
+    };
+    let body = |body: &[ASTPoint]| body.iter().map(|x| uncache(x, texts, cached)).collect();
+    match point {
+        ASTPoint::Element(x) => ASTPoint::Element(node(x)),
+        ASTPoint::Joint(x, children) => ASTPoint::Joint(node(x), body(children)),
+        ASTPoint::If(x, children) => ASTPoint::If(x.clone(), body(children)),
+        ASTPoint::For(x, children) => ASTPoint::For(x.clone(), body(children)),
+        ASTPoint::Use(_) | ASTPoint::Include(_) => point.clone(),
+    }
+}
+
+impl fmt::Debug for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.state();
+        f.debug_struct("Document")
+            .field("filename", &state.filename)
+            .field("version", &state.version)
+            .field("error", &state.error)
+            .finish()
+    }
+}
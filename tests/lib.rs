@@ -14,7 +14,8 @@ impl Layout for MyDataModel {
     fn layout(&self, info: WindowInfo<Self>) -> Dom<Self> {
         if let Some(ref texts) = self.texts{
             if let Some(ref ast) = self.ast{
-                return consume_ast(&ast, &texts, &info)
+                return consume_ast(&ast, &texts, &info, &CallbackRegistry::new(), &())
+                    .unwrap_or_else(|e| Dom::new(NodeType::Label(e.to_string())))
             }
         }
         panic!("Can't find ast and text inside of MyDataModel");
@@ -33,8 +34,8 @@ fn test_it() {
     let css = Css::new_from_str(include_str!(CSS_PATH!())).unwrap();
 
     let mut app = App::new(MyDataModel{ast: None, texts: None}, AppConfig::default());
-    let (texts, ast) = load_resources(".\\tests\\test.azd");
-    let textids = create_resources(&mut app, VecDeque::from(texts), &vec![(Box::new(*ubuntu_font), "Ubuntu-Regular")], Vec::new());
+    let (texts, ast) = include_azd!("../azd-syntax/tests/test.azd").unwrap();
+    let textids = create_resources(&mut app, VecDeque::from(texts), &vec![(Box::new(*ubuntu_font), "Ubuntu-Regular")], Vec::new()).unwrap();
     (*app.app_state.data.lock().unwrap()).ast = Some(ast);
     (*app.app_state.data.lock().unwrap()).texts = Some(textids);
     app.run(Window::new(WindowCreateOptions::default(), css).unwrap())
@@ -0,0 +1,13 @@
+extern crate azul_document_loader;
+
+use azul_document_loader::*;
+
+#[test]
+fn documents_can_be_embedded() {
+    let (texts, _) = include_azd!("../azd-syntax/tests/include/parts/logo.azd").unwrap();
+    assert_eq!(texts[0].body, "logo");
+    match include_azd!("../azd-syntax/tests/include/main.azd") {
+        Err(LoadError::Include(x)) => assert_eq!(x.span.line, 2),
+        x => panic!("Expected an include error, got {:?}", x),
+    }
+}
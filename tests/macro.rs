@@ -0,0 +1,47 @@
+extern crate azul;
+extern crate azul_document_loader;
+
+use azul::prelude::*;
+use azul_document_loader::*;
+
+struct Model;
+
+impl Layout for Model {
+    fn layout(&self, _: WindowInfo<Self>) -> Dom<Self> {
+        Dom::new(NodeType::Div)
+    }
+}
+
+// Nothing is left to do at runtime, so documents can be kept in statics.
+static MAIN: Embedded<Model> = azd!("azd-syntax/tests/include/main.azd");
+
+#[test]
+fn documents_are_parsed_at_compile_time() {
+    let (expected, _) =
+        load::load_document(concat!(env!("CARGO_MANIFEST_DIR"), "/azd-syntax/tests/include/main.azd")).unwrap();
+    assert_eq!(MAIN.texts(), expected);
+}
+
+#[test]
+fn documents_can_be_written_inline() {
+    let layout: Embedded<Model> = azd! {
+        div#root.dark-mode(tabindex = 0)[
+            {A comment}
+            text("hello" "font" 10.5pt line_height = 150%);
+            text("Hello {name}");
+            if(!logged_in)[
+                label.hint("Please log in");
+            ]
+        ]
+    };
+    // Texts with bindings are built along with the rest of the `Dom`.
+    let texts = layout.texts();
+    assert_eq!(texts.len(), 1);
+    assert_eq!(texts[0].body, "hello");
+    assert_eq!(texts[0].font.as_deref(), Some("font"));
+    let size = tokenize::Number {
+        value: 10.5,
+        unit: Some(tokenize::Unit::Pt),
+    };
+    assert_eq!(texts[0].size, Some(size));
+}
@@ -0,0 +1,46 @@
+extern crate azul_document_loader;
+
+use azul_document_loader::reload::Document;
+use azul_document_loader::*;
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn documents_are_reloaded_when_they_change() {
+    let path = std::env::temp_dir().join(format!("azd-reload-{}.azd", std::process::id()));
+    let filename = path.to_str().unwrap();
+    fs::write(&path, "div[ text (\"a\"); ]").unwrap();
+    // Reload by hand instead of waiting on the watcher.
+    let document = Document::watch(filename, Duration::from_secs(3600)).unwrap();
+    assert!(!document.reload());
+
+    fs::write(&path, "div[ text (\"b {{x}}\"); label (\"new\"); ]").unwrap();
+    assert!(document.reload());
+    assert_eq!(document.version(), 1);
+    assert_eq!(document.texts()[0].body, "b {x}");
+    // The cached text is gone, so the new one is laid out like a label.
+    let cached_ast = document.cached_ast();
+    // It is only made again when the document changes.
+    assert!(Arc::ptr_eq(&cached_ast, &document.cached_ast()));
+    match *cached_ast {
+        parse::ASTPoint::Joint(_, ref body) => match body[0] {
+            parse::ASTPoint::Element(parse::Node {
+                ntype: parse::NType::BoundText(ref x),
+                ..
+            }) => assert_eq!(x.body, "b {{x}}"),
+            ref x => panic!("Expected a bound text, got {:?}", x),
+        },
+        ref x => panic!("Expected a joint, got {:?}", x),
+    }
+
+    fs::write(&path, "div[ label (12); ]").unwrap();
+    assert!(document.reload());
+    assert_eq!(document.version(), 1);
+    assert!(document.error().unwrap().contains("Labels take a string"));
+    match *document.ast() {
+        parse::ASTPoint::Joint(_, ref body) => assert_eq!(body.len(), 2),
+        ref x => panic!("Expected the last good tree, got {:?}", x),
+    }
+    fs::remove_file(&path).unwrap();
+}